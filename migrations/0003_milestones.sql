@@ -0,0 +1,14 @@
+CREATE TABLE milestones (
+    milestone_id SERIAL PRIMARY KEY,
+    offer_id INT NOT NULL REFERENCES offers(offer_id) ON DELETE CASCADE,
+    amount NUMERIC(10, 2) NOT NULL CHECK (amount >= 0),
+    due_date TIMESTAMPTZ NOT NULL,
+    deliverable TEXT NOT NULL,
+    status VARCHAR(17) NOT NULL DEFAULT 'pending',
+    feedback TEXT,
+    submitted_at TIMESTAMPTZ,
+    approved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX milestones_offer_id_idx ON milestones (offer_id);
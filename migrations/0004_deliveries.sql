@@ -0,0 +1,33 @@
+ALTER TABLE orders ADD COLUMN status VARCHAR(16) NOT NULL DEFAULT 'open';
+
+UPDATE orders SET status = 'assigned'
+WHERE EXISTS (
+    SELECT 1 FROM offers
+    WHERE offers.order_id = orders.order_id AND offers.status = 'accepted'
+);
+
+CREATE TABLE deliveries (
+    delivery_id SERIAL PRIMARY KEY,
+    order_id INT NOT NULL REFERENCES orders(order_id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    note TEXT NOT NULL,
+    status VARCHAR(18) NOT NULL DEFAULT 'pending',
+    feedback TEXT,
+    reviewed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX deliveries_order_id_idx ON deliveries (order_id);
+
+-- Only one delivery of an order can wait for review at a time
+CREATE UNIQUE INDEX deliveries_pending_order_id_idx ON deliveries (order_id) WHERE status = 'pending';
+
+CREATE TABLE delivery_files (
+    file_id SERIAL PRIMARY KEY,
+    delivery_id INT NOT NULL REFERENCES deliveries(delivery_id) ON DELETE CASCADE,
+    storage_key TEXT NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size BIGINT NOT NULL
+);
+
+CREATE INDEX delivery_files_delivery_id_idx ON delivery_files (delivery_id);
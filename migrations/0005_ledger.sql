@@ -0,0 +1,75 @@
+-- Accounts of deleted users are closed rather than deleted, so no entry loses a leg
+CREATE TABLE accounts (
+    account_id SERIAL PRIMARY KEY,
+    kind VARCHAR(8) NOT NULL,
+    user_id INT UNIQUE REFERENCES users(user_id) ON DELETE SET NULL,
+    order_id INT UNIQUE REFERENCES orders(order_id) ON DELETE SET NULL,
+    closed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE UNIQUE INDEX accounts_single_system_idx ON accounts (kind) WHERE kind = 'system';
+
+CREATE TABLE journal_entries (
+    entry_id SERIAL PRIMARY KEY,
+    order_id INT REFERENCES orders(order_id) ON DELETE SET NULL,
+    kind VARCHAR(16) NOT NULL,
+    description TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Positive amounts credit the account, negative amounts debit it
+CREATE TABLE postings (
+    posting_id SERIAL PRIMARY KEY,
+    entry_id INT NOT NULL REFERENCES journal_entries(entry_id) ON DELETE RESTRICT,
+    account_id INT NOT NULL REFERENCES accounts(account_id) ON DELETE RESTRICT,
+    amount NUMERIC(12, 2) NOT NULL
+);
+
+CREATE INDEX postings_account_id_idx ON postings (account_id);
+CREATE INDEX postings_entry_id_idx ON postings (entry_id);
+
+-- Checks the entries on both sides of an update, and what's left of an entry
+-- after a delete
+CREATE OR REPLACE FUNCTION check_entry_balanced()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP <> 'DELETE' THEN
+        IF (SELECT COALESCE(SUM(amount), 0) FROM postings WHERE entry_id = NEW.entry_id) <> 0 THEN
+            RAISE EXCEPTION 'journal entry % is not balanced', NEW.entry_id;
+        END IF;
+    END IF;
+    IF TG_OP <> 'INSERT' THEN
+        IF (SELECT COALESCE(SUM(amount), 0) FROM postings WHERE entry_id = OLD.entry_id) <> 0 THEN
+            RAISE EXCEPTION 'journal entry % is not balanced', OLD.entry_id;
+        END IF;
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE CONSTRAINT TRIGGER postings_balanced
+AFTER INSERT OR UPDATE OR DELETE ON postings
+DEFERRABLE INITIALLY DEFERRED
+FOR EACH ROW
+EXECUTE FUNCTION check_entry_balanced();
+
+INSERT INTO accounts (kind) VALUES ('system');
+
+-- Existing users get the same starting balance new registrations do
+DO $$
+DECLARE
+    u RECORD;
+    entry INT;
+    account INT;
+BEGIN
+    FOR u IN SELECT user_id FROM users LOOP
+        INSERT INTO accounts (kind, user_id) VALUES ('user', u.user_id) RETURNING account_id INTO account;
+        INSERT INTO journal_entries (kind, description) VALUES ('grant', 'Starting credits') RETURNING entry_id INTO entry;
+        INSERT INTO postings (entry_id, account_id, amount) VALUES
+            (entry, account, 100.00),
+            (entry, (SELECT account_id FROM accounts WHERE kind = 'system'), -100.00);
+    END LOOP;
+END;
+$$;
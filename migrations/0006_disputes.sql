@@ -0,0 +1,41 @@
+-- Moderators are promoted by hand: UPDATE users SET role = 'moderator' WHERE ...
+ALTER TABLE users ADD COLUMN role VARCHAR(16) NOT NULL DEFAULT 'user';
+
+CREATE TABLE disputes (
+    dispute_id SERIAL PRIMARY KEY,
+    order_id INT NOT NULL REFERENCES orders(order_id) ON DELETE CASCADE,
+    opened_by INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    reason TEXT NOT NULL,
+    status VARCHAR(8) NOT NULL DEFAULT 'open',
+    resolution VARCHAR(8),
+    contractor_amount NUMERIC(10, 2),
+    resolution_note TEXT,
+    resolved_by INT REFERENCES users(user_id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX disputes_order_id_idx ON disputes (order_id);
+
+-- An order can only be in one dispute at a time
+CREATE UNIQUE INDEX disputes_open_order_id_idx ON disputes (order_id) WHERE status = 'open';
+
+CREATE TABLE dispute_evidence (
+    file_id SERIAL PRIMARY KEY,
+    dispute_id INT NOT NULL REFERENCES disputes(dispute_id) ON DELETE CASCADE,
+    storage_key TEXT NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size BIGINT NOT NULL
+);
+
+CREATE INDEX dispute_evidence_dispute_id_idx ON dispute_evidence (dispute_id);
+
+CREATE TABLE dispute_messages (
+    message_id SERIAL PRIMARY KEY,
+    dispute_id INT NOT NULL REFERENCES disputes(dispute_id) ON DELETE CASCADE,
+    sender_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX dispute_messages_dispute_id_idx ON dispute_messages (dispute_id);
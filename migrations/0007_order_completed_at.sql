@@ -0,0 +1,7 @@
+ALTER TABLE orders ADD COLUMN completed_at TIMESTAMPTZ;
+
+UPDATE orders SET completed_at = (
+    SELECT MAX(reviewed_at) FROM deliveries
+    WHERE deliveries.order_id = orders.order_id AND deliveries.status = 'accepted'
+)
+WHERE status = 'completed';
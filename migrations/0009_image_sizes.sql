@@ -0,0 +1,18 @@
+ALTER TABLE uploads
+    ADD COLUMN medium_url TEXT,
+    ADD COLUMN medium_key TEXT,
+    ADD COLUMN thumbnail_url TEXT,
+    ADD COLUMN thumbnail_key TEXT;
+
+-- Uploads from before processing only exist in their original size
+UPDATE uploads SET
+    medium_url = url,
+    medium_key = storage_key,
+    thumbnail_url = url,
+    thumbnail_key = storage_key;
+
+ALTER TABLE uploads
+    ALTER COLUMN medium_url SET NOT NULL,
+    ALTER COLUMN medium_key SET NOT NULL,
+    ALTER COLUMN thumbnail_url SET NOT NULL,
+    ALTER COLUMN thumbnail_key SET NOT NULL;
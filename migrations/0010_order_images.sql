@@ -0,0 +1,23 @@
+CREATE TABLE order_images (
+    image_id SERIAL PRIMARY KEY,
+    order_id INT NOT NULL REFERENCES orders(order_id) ON DELETE CASCADE,
+    upload_id INT NOT NULL REFERENCES uploads(upload_id),
+    position INT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX order_images_order_id_idx ON order_images (order_id, position);
+CREATE INDEX order_images_upload_id_idx ON order_images (upload_id);
+
+-- Images attached before uploads were tracked get an upload of their own.
+-- Their URL doubles as the key, which is what the catbox store uses anyway.
+INSERT INTO uploads (user_id, storage_key, url, medium_key, medium_url, thumbnail_key, thumbnail_url, content_type, size)
+SELECT DISTINCT ON (img.url) o.user_id, img.url, img.url, img.url, img.url, img.url, img.url, 'application/octet-stream', 0
+FROM orders o, unnest(o.image_urls) AS img(url)
+WHERE NOT EXISTS (SELECT 1 FROM uploads u WHERE u.url = img.url);
+
+INSERT INTO order_images (order_id, upload_id, position)
+SELECT o.order_id, (SELECT MIN(u.upload_id) FROM uploads u WHERE u.url = img.url), img.position
+FROM orders o, unnest(o.image_urls) WITH ORDINALITY AS img(url, position);
+
+ALTER TABLE orders DROP COLUMN image_urls;
@@ -0,0 +1,13 @@
+CREATE TABLE attachments (
+    attachment_id SERIAL PRIMARY KEY,
+    order_id INT NOT NULL REFERENCES orders(order_id) ON DELETE CASCADE,
+    user_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    file_name TEXT NOT NULL,
+    storage_key TEXT NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size BIGINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX attachments_order_id_idx ON attachments (order_id);
+CREATE INDEX attachments_user_id_idx ON attachments (user_id);
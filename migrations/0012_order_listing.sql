@@ -0,0 +1,6 @@
+ALTER TABLE orders ADD COLUMN deadline TIMESTAMPTZ;
+
+-- Keyset pagination walks these in (sort key, order_id) order
+CREATE INDEX orders_created_at_idx ON orders (created_at, order_id);
+CREATE INDEX orders_price_idx ON orders (price, order_id);
+CREATE INDEX orders_deadline_idx ON orders ((COALESCE(deadline, 'infinity'::TIMESTAMPTZ)), order_id);
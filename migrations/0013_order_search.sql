@@ -0,0 +1,19 @@
+-- Postgres ships no Polish stemmer, so Polish text is only lowercased and
+-- split into words, which still beats matching on the title alone
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_ts_config WHERE cfgname = 'polish') THEN
+        CREATE TEXT SEARCH CONFIGURATION polish (COPY = simple);
+    END IF;
+END
+$$;
+
+ALTER TABLE orders ADD COLUMN search_vector TSVECTOR GENERATED ALWAYS AS (
+    setweight(to_tsvector('english'::REGCONFIG, order_name), 'A') ||
+    setweight(to_tsvector('polish'::REGCONFIG, order_name), 'A') ||
+    setweight(to_tsvector('english'::REGCONFIG, order_desc), 'B') ||
+    setweight(to_tsvector('polish'::REGCONFIG, order_desc), 'B')
+) STORED;
+
+CREATE INDEX orders_search_vector_idx ON orders USING GIN (search_vector);
+CREATE INDEX orders_order_name_trgm_idx ON orders USING GIN (order_name gin_trgm_ops);
@@ -0,0 +1,28 @@
+CREATE TABLE categories (
+    category_id SERIAL PRIMARY KEY,
+    parent_id INT REFERENCES categories(category_id),
+    name VARCHAR(50) NOT NULL,
+    slug VARCHAR(50) NOT NULL UNIQUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX categories_parent_id_idx ON categories (parent_id);
+
+ALTER TABLE orders ADD COLUMN category_id INT REFERENCES categories(category_id) ON DELETE SET NULL;
+
+CREATE INDEX orders_category_id_idx ON orders (category_id);
+
+CREATE TABLE tags (
+    tag_id SERIAL PRIMARY KEY,
+    name VARCHAR(32) NOT NULL UNIQUE
+);
+
+CREATE INDEX tags_name_pattern_idx ON tags (name text_pattern_ops);
+
+CREATE TABLE order_tags (
+    order_id INT NOT NULL REFERENCES orders(order_id) ON DELETE CASCADE,
+    tag_id INT NOT NULL REFERENCES tags(tag_id) ON DELETE CASCADE,
+    PRIMARY KEY (order_id, tag_id)
+);
+
+CREATE INDEX order_tags_tag_id_idx ON order_tags (tag_id);
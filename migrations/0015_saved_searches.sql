@@ -0,0 +1,29 @@
+CREATE TABLE saved_searches (
+    search_id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    query TEXT NOT NULL DEFAULT '',
+    category_id INT REFERENCES categories(category_id) ON DELETE CASCADE,
+    tags TEXT ARRAY NOT NULL DEFAULT '{}',
+    min_price NUMERIC(10, 2),
+    max_price NUMERIC(10, 2),
+    email_digest BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX saved_searches_user_id_idx ON saved_searches (user_id);
+
+CREATE TABLE notifications (
+    notification_id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    kind VARCHAR(32) NOT NULL,
+    order_id INT REFERENCES orders(order_id) ON DELETE CASCADE,
+    message TEXT NOT NULL,
+    -- Still has to go out in the next email digest
+    email_pending BOOLEAN NOT NULL DEFAULT FALSE,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX notifications_user_id_idx ON notifications (user_id, created_at);
+CREATE INDEX notifications_email_pending_idx ON notifications (user_id) WHERE email_pending;
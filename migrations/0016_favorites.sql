@@ -0,0 +1,10 @@
+CREATE TABLE favorites (
+    user_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    order_id INT NOT NULL REFERENCES orders(order_id) ON DELETE CASCADE,
+    -- Set once the "deadline is close" reminder went out
+    expiry_notified BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, order_id)
+);
+
+CREATE INDEX favorites_order_id_idx ON favorites (order_id);
@@ -0,0 +1,2 @@
+-- Prices were implicitly in the currency the credits are kept in
+ALTER TABLE orders ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'PLN' CHECK (currency ~ '^[A-Z]{3}$');
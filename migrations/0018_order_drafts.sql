@@ -0,0 +1,4 @@
+ALTER TABLE orders ADD COLUMN publish_at TIMESTAMPTZ;
+-- Only drafts can wait to be published
+ALTER TABLE orders ADD CONSTRAINT orders_publish_at_check CHECK (publish_at IS NULL OR status = 'draft');
+CREATE INDEX orders_publish_at_idx ON orders (publish_at) WHERE status = 'draft';
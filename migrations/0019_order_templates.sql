@@ -0,0 +1,24 @@
+CREATE TABLE order_templates (
+    template_id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    order_name VARCHAR(100) NOT NULL,
+    order_desc TEXT NOT NULL,
+    price NUMERIC(10, 2) NOT NULL CHECK (price >= 0),
+    currency CHAR(3) NOT NULL DEFAULT 'PLN' CHECK (currency ~ '^[A-Z]{3}$'),
+    category_id INT REFERENCES categories(category_id) ON DELETE SET NULL,
+    -- Tag names, they only become rows in tags once an order uses them
+    tags TEXT ARRAY NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX order_templates_user_id_idx ON order_templates (user_id);
+
+CREATE TABLE template_images (
+    template_id INT NOT NULL REFERENCES order_templates(template_id) ON DELETE CASCADE,
+    upload_id INT NOT NULL REFERENCES uploads(upload_id),
+    position INT NOT NULL,
+    PRIMARY KEY (template_id, position)
+);
+
+CREATE INDEX template_images_upload_id_idx ON template_images (upload_id);
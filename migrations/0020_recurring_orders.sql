@@ -0,0 +1,19 @@
+-- Each series has one row, attached to its latest order. New orders are
+-- copied from that one and the row moves along to them.
+CREATE TABLE order_recurrences (
+    order_id INT PRIMARY KEY REFERENCES orders(order_id) ON DELETE CASCADE,
+    rule TEXT NOT NULL,
+    starts_at TIMESTAMPTZ NOT NULL,
+    -- When the current order of the series was due, and the next one is
+    last_at TIMESTAMPTZ NOT NULL,
+    next_at TIMESTAMPTZ NOT NULL,
+    occurrences INT NOT NULL DEFAULT 1,
+    -- Reserve new orders for the previous contractor for a while
+    auto_offer BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX order_recurrences_next_at_idx ON order_recurrences (next_at);
+
+-- Contractor a draft is reserved for before it goes public
+ALTER TABLE orders ADD COLUMN invited_user_id INT REFERENCES users(user_id) ON DELETE SET NULL;
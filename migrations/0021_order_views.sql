@@ -0,0 +1,10 @@
+-- One row per order and viewer, `user:<id>` when signed in and
+-- `session:<id>` for anonymous visitors sending X-Session-Id
+CREATE TABLE order_views (
+    order_id INT NOT NULL REFERENCES orders(order_id) ON DELETE CASCADE,
+    viewer VARCHAR(80) NOT NULL,
+    viewed_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (order_id, viewer)
+);
+
+CREATE INDEX offers_order_id_idx ON offers (order_id, created_at);
@@ -0,0 +1,8 @@
+ALTER TABLE orders ADD COLUMN remote BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE orders ADD COLUMN latitude DOUBLE PRECISION CHECK (latitude BETWEEN -90 AND 90);
+ALTER TABLE orders ADD COLUMN longitude DOUBLE PRECISION CHECK (longitude BETWEEN -180 AND 180);
+ALTER TABLE orders ADD CONSTRAINT orders_coordinates_check CHECK ((latitude IS NULL) = (longitude IS NULL));
+ALTER TABLE orders ADD COLUMN city VARCHAR(100);
+ALTER TABLE orders ADD COLUMN school VARCHAR(100);
+-- Radius searches narrow orders down to a band of latitudes before measuring
+CREATE INDEX orders_latitude_idx ON orders (latitude) WHERE latitude IS NOT NULL;
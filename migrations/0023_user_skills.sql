@@ -0,0 +1,2 @@
+-- Normalized like order tags so the two can be matched
+ALTER TABLE users ADD COLUMN skills TEXT[] NOT NULL DEFAULT '{}';
@@ -0,0 +1,12 @@
+-- One row per edit of a published order, with the old and new value of every
+-- field it changed
+CREATE TABLE order_revisions (
+    revision_id SERIAL PRIMARY KEY,
+    order_id INT NOT NULL REFERENCES orders(order_id) ON DELETE CASCADE,
+    changes JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX order_revisions_order_id_idx ON order_revisions (order_id, created_at);
+
+ALTER TABLE orders ADD COLUMN edited_at TIMESTAMPTZ;
@@ -0,0 +1,43 @@
+-- Reports point at any kind of content, so the target can't have a foreign
+-- key and may be gone by the time a moderator looks at it
+CREATE TABLE reports (
+    report_id SERIAL PRIMARY KEY,
+    reporter_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    target_type VARCHAR(8) NOT NULL CHECK (target_type IN ('order', 'offer', 'message', 'review', 'user')),
+    target_id INT NOT NULL,
+    reason VARCHAR(16) NOT NULL,
+    details TEXT,
+    status VARCHAR(9) NOT NULL DEFAULT 'open' CHECK (status IN ('open', 'dismissed', 'actioned')),
+    action_id INT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- A user can only have one open report per target
+CREATE UNIQUE INDEX reports_open_idx ON reports (reporter_id, target_type, target_id) WHERE status = 'open';
+CREATE INDEX reports_target_idx ON reports (target_type, target_id);
+CREATE INDEX reports_status_idx ON reports (status, created_at);
+
+-- Audit log of every moderation decision, with a copy of the target as it
+-- was when the decision was made
+CREATE TABLE moderation_actions (
+    action_id SERIAL PRIMARY KEY,
+    moderator_id INT REFERENCES users(user_id) ON DELETE SET NULL,
+    action VARCHAR(8) NOT NULL CHECK (action IN ('dismiss', 'hide', 'delete', 'warn', 'ban')),
+    target_type VARCHAR(8) NOT NULL,
+    target_id INT NOT NULL,
+    -- Whoever wrote the content, or the reported user
+    target_user_id INT REFERENCES users(user_id) ON DELETE SET NULL,
+    snapshot JSONB NOT NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX moderation_actions_target_idx ON moderation_actions (target_type, target_id);
+
+ALTER TABLE reports ADD CONSTRAINT reports_action_id_fkey FOREIGN KEY (action_id) REFERENCES moderation_actions(action_id) ON DELETE SET NULL;
+
+ALTER TABLE orders ADD COLUMN hidden_at TIMESTAMPTZ;
+ALTER TABLE offers ADD COLUMN hidden_at TIMESTAMPTZ;
+ALTER TABLE messages ADD COLUMN hidden_at TIMESTAMPTZ;
+ALTER TABLE reviews ADD COLUMN hidden_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN banned_at TIMESTAMPTZ;
@@ -0,0 +1,45 @@
+-- Suspensions without an end are permanent bans
+CREATE TABLE suspensions (
+    suspension_id SERIAL PRIMARY KEY,
+    user_id INT NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+    moderator_id INT REFERENCES users(user_id) ON DELETE SET NULL,
+    reason TEXT NOT NULL,
+    ends_at TIMESTAMPTZ,
+    lifted_at TIMESTAMPTZ,
+    lifted_by INT REFERENCES users(user_id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX suspensions_user_idx ON suspensions (user_id) WHERE lifted_at IS NULL;
+
+-- One appeal per suspension
+CREATE TABLE appeals (
+    appeal_id SERIAL PRIMARY KEY,
+    suspension_id INT NOT NULL UNIQUE REFERENCES suspensions(suspension_id) ON DELETE CASCADE,
+    message TEXT NOT NULL,
+    status VARCHAR(8) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'accepted', 'rejected')),
+    response TEXT,
+    reviewed_by INT REFERENCES users(user_id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX appeals_status_idx ON appeals (status, created_at);
+
+-- Bans from the moderation queue become permanent suspensions, with the
+-- moderator and note of the decision behind them where it's still logged
+INSERT INTO suspensions (user_id, moderator_id, reason, created_at)
+SELECT u.user_id, a.moderator_id, COALESCE(a.note, 'Banned by a moderator'), u.banned_at
+FROM users u
+LEFT JOIN LATERAL (
+    SELECT moderator_id, note FROM moderation_actions
+    WHERE action = 'ban' AND target_user_id = u.user_id
+    ORDER BY created_at DESC LIMIT 1
+) a ON TRUE
+WHERE u.banned_at IS NOT NULL;
+
+ALTER TABLE users DROP COLUMN banned_at;
+
+ALTER TABLE moderation_actions DROP CONSTRAINT moderation_actions_action_check;
+ALTER TABLE moderation_actions ADD CONSTRAINT moderation_actions_action_check
+    CHECK (action IN ('dismiss', 'hide', 'delete', 'warn', 'suspend', 'ban'));
@@ -0,0 +1,9 @@
+-- Evidence counts against the storage quota of whoever sent it. What was sent
+-- before goes to whoever opened the dispute.
+ALTER TABLE dispute_evidence ADD COLUMN user_id INT REFERENCES users(user_id) ON DELETE SET NULL;
+
+UPDATE dispute_evidence e SET user_id = d.opened_by
+FROM disputes d
+WHERE d.dispute_id = e.dispute_id;
+
+CREATE INDEX dispute_evidence_user_id_idx ON dispute_evidence (user_id);
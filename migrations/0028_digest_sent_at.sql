@@ -0,0 +1,4 @@
+-- When digests went out, so a restart doesn't reset the schedule and a failed
+-- email doesn't mark its notifications as sent
+ALTER TABLE users ADD COLUMN last_digest_at TIMESTAMPTZ;
+ALTER TABLE notifications ADD COLUMN emailed_at TIMESTAMPTZ;
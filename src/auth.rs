@@ -1,7 +1,7 @@
-use std::fmt::Debug;
+use std::{convert::Infallible, fmt::Debug};
 
 use axum::{
-    extract::{FromRequestParts, Query},
+    extract::{FromRequestParts, OptionalFromRequestParts, Query},
     http::{request::Parts, StatusCode},
     RequestPartsExt,
 };
@@ -9,15 +9,23 @@ use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
+use hmac::{Hmac, Mac};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Validation};
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::{
+    db::{postgres::PostgresDb, Db},
+    AppState,
+};
 
 static KEYS: OnceCell<Keys> = OnceCell::new();
 
 struct Keys {
     enc: EncodingKey,
     dec: DecodingKey,
+    links: Vec<u8>,
 }
 
 impl Debug for Keys {
@@ -30,10 +38,19 @@ pub fn init_keys(secret: &[u8]) {
     let keys = Keys {
         enc: EncodingKey::from_secret(secret),
         dec: DecodingKey::from_secret(secret),
+        links: derive_key(secret, b"attachment-links"),
     };
     KEYS.set(keys).expect("Keys already initialized");
 }
 
+/// Derives a key for another purpose, so a signed link can never be used
+/// against the JWT key or the other way around.
+fn derive_key(secret: &[u8], purpose: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(purpose);
+    mac.finalize().into_bytes().to_vec()
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Claims {
     pub sub: usize,
@@ -46,13 +63,15 @@ struct Token {
     token: String,
 }
 
-impl<S> FromRequestParts<S> for Claims
-where
-    S: Send + Sync,
-{
+/// Tokens of suspended users are refused, so a suspension takes effect right
+/// away rather than when the token expires.
+impl FromRequestParts<AppState<PostgresDb>> for Claims {
     type Rejection = StatusCode;
 
-    async fn from_request_parts(parts: &mut Parts, _: &S) -> Result<Self, Self::Rejection> {
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<PostgresDb>,
+    ) -> Result<Self, Self::Rejection> {
         let token;
         if let Ok(TypedHeader(Authorization(bearer))) =
             parts.extract::<TypedHeader<Authorization<Bearer>>>().await
@@ -63,13 +82,35 @@ where
         } else {
             return Err(StatusCode::UNAUTHORIZED);
         }
-        decode::<Claims>(
+        let claims = decode::<Claims>(
             &token,
             &KEYS.get().expect("Keys not initialized").dec,
             &Validation::default(),
         )
         .map(|data| data.claims)
-        .map_err(|_| StatusCode::UNAUTHORIZED)
+        .map_err(|_| StatusCode::UNAUTHORIZED)?;
+        match state.db.get_active_suspension(claims.sub).await {
+            Ok(None) => Ok(claims),
+            Ok(Some(_)) => Err(StatusCode::FORBIDDEN),
+            Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+        }
+    }
+}
+
+/// Lets handlers serve anonymous visitors while still recognising signed in
+/// users, a missing or invalid token just means no claims.
+impl OptionalFromRequestParts<AppState<PostgresDb>> for Claims {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState<PostgresDb>,
+    ) -> Result<Option<Self>, Self::Rejection> {
+        Ok(
+            <Claims as FromRequestParts<AppState<PostgresDb>>>::from_request_parts(parts, state)
+                .await
+                .ok(),
+        )
     }
 }
 
@@ -90,3 +131,24 @@ pub fn create_jwt(user_id: usize) -> Result<String, String> {
     )
     .map_err(|e| e.to_string())
 }
+
+fn link_mac(path: &str, expires: u64) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&KEYS.get().expect("Keys not initialized").links)
+        .expect("HMAC accepts keys of any length");
+    mac.update(format!("{path}\n{expires}").as_bytes());
+    mac
+}
+
+/// Signs `path` so it can be fetched without a token until `expires` (unix seconds).
+pub fn sign_link(path: &str, expires: u64) -> String {
+    hex::encode(link_mac(path, expires).finalize().into_bytes())
+}
+
+pub fn verify_link(path: &str, expires: u64, signature: &str) -> bool {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(u64::MAX, |now| now.as_secs());
+    expires >= now
+        && hex::decode(signature)
+            .is_ok_and(|signature| link_mac(path, expires).verify_slice(&signature).is_ok())
+}
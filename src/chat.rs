@@ -6,9 +6,6 @@ use axum::{
     response::Response,
 };
 
-use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
-
 use crate::{
     auth::Claims,
     db::{MessageInput, MsgListner},
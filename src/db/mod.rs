@@ -1,3 +1,6 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+
 pub mod model;
 pub use model::*;
 pub mod postgres;
@@ -5,28 +8,345 @@ pub mod postgres;
 pub trait Db {
     type MsgListner: MsgListner;
 
-    async fn create_user(&self, user: UserInput) -> Result<User, String>;
+    /// Creates the user and opens their wallet with `credits` granted, all or
+    /// nothing.
+    async fn create_user(&self, user: UserInput, credits: Decimal) -> Result<User, String>;
     async fn get_user_by_id(&self, user_id: usize) -> Result<Option<User>, String>;
+    #[allow(dead_code)]
     async fn get_user_by_email(&self, email: &str) -> Result<Option<User>, String>;
     async fn get_user_by_username(&self, username: &str) -> Result<Option<User>, String>;
     async fn search_users(&self, query: &str) -> Result<Vec<User>, String>;
     async fn update_user(&self, user_id: usize, user: UserInput) -> Result<User, String>;
-    async fn delete_user(&self, user_id: usize) -> Result<(), String>;
+    /// Replaces the skills on the user's profile.
+    async fn set_user_skills(&self, user_id: usize, skills: &[String]) -> Result<User, String>;
+    /// Returns `false` without deleting anything while the user is a party to
+    /// an order in progress or in dispute.
+    async fn delete_user(&self, user_id: usize) -> Result<bool, String>;
 
-    async fn create_order(&self, order: OrderInput, user_id: usize) -> Result<Order, String>;
+    async fn create_order(
+        &self,
+        order: OrderInput,
+        upload_ids: &[usize],
+        user_id: usize,
+        draft: bool,
+    ) -> Result<Order, String>;
     async fn get_order_by_id(&self, order_id: usize) -> Result<Option<Order>, String>;
+    /// Only published orders, drafts come from `get_drafts_by_user_id`.
     async fn get_orders_by_user_id(&self, user_id: usize) -> Result<Vec<Order>, String>;
-    async fn search_orders(&self, query: &str) -> Result<Vec<Order>, String>;
-    async fn update_order(&self, order_id: usize, order: OrderInput) -> Result<Order, String>;
+    async fn get_drafts_by_user_id(&self, user_id: usize) -> Result<Vec<Order>, String>;
+    async fn search_orders(&self, search: &OrderSearch) -> Result<OrderSearchResults, String>;
+    async fn list_orders(&self, filter: &OrderFilter) -> Result<Vec<Order>, String>;
+    /// Also records a revision when a published order changes. `None` when
+    /// the price or currency would change on an order that's no longer a
+    /// draft or open.
+    async fn update_order(
+        &self,
+        order_id: usize,
+        order: OrderInput,
+    ) -> Result<Option<Order>, String>;
+    /// Newest first.
+    async fn get_order_revisions(&self, order_id: usize) -> Result<Vec<OrderRevision>, String>;
+    /// Cancels an open or assigned order and refunds the escrow, all or
+    /// nothing.
+    async fn cancel_order(&self, order_id: usize) -> Result<CancelOutcome, String>;
     async fn delete_order(&self, order_id: usize) -> Result<(), String>;
+    /// Makes a draft public, it counts as posted from now on. `None` when the
+    /// order isn't a draft (anymore).
+    async fn publish_order(&self, order_id: usize) -> Result<Option<Order>, String>;
+    async fn schedule_order(
+        &self,
+        order_id: usize,
+        publish_at: DateTime<Utc>,
+    ) -> Result<Order, String>;
+    /// Publishes every draft whose time has come and returns their ids.
+    async fn publish_due_orders(&self) -> Result<Vec<usize>, String>;
+
+    /// Counts a view unless `viewer` has seen the order before.
+    async fn record_order_view(&self, order_id: usize, viewer: &str) -> Result<(), String>;
+    async fn get_order_stats(&self, order_id: usize) -> Result<OrderStats, String>;
+    async fn get_user_order_stats(&self, user_id: usize) -> Result<UserOrderStats, String>;
+
+    /// Orders the user had an offer accepted for.
+    async fn get_past_work(&self, user_id: usize) -> Result<Vec<PastWork>, String>;
+    /// The newest open orders the user could still make an offer for.
+    async fn get_recommendation_candidates(
+        &self,
+        user_id: usize,
+        limit: usize,
+    ) -> Result<Vec<RecommendationCandidate>, String>;
+
+    /// Creates or replaces the recurrence of the order.
+    async fn set_order_recurrence(&self, recurrence: &OrderRecurrence) -> Result<(), String>;
+    async fn get_order_recurrence(
+        &self,
+        order_id: usize,
+    ) -> Result<Option<OrderRecurrence>, String>;
+    /// Returns `false` if the order had no recurrence.
+    async fn delete_order_recurrence(&self, order_id: usize) -> Result<bool, String>;
+    async fn get_due_recurrences(&self) -> Result<Vec<OrderRecurrence>, String>;
+    /// Copies the latest order of the series into a new one due at
+    /// `recurrence.next_at` and moves the recurrence over to it, or ends the
+    /// series if there's no `next_at`. With an `invite` the order starts as a
+    /// draft reserved for that user until the given time.
+    async fn create_occurrence(
+        &self,
+        recurrence: &OrderRecurrence,
+        next_at: Option<DateTime<Utc>>,
+        invite: Option<(usize, DateTime<Utc>)>,
+    ) -> Result<Order, String>;
+    async fn has_completed_order_between(
+        &self,
+        user1_id: usize,
+        user2_id: usize,
+    ) -> Result<bool, String>;
+
+    async fn create_category(&self, category: CategoryInput) -> Result<Category, String>;
+    async fn get_categories(&self) -> Result<Vec<Category>, String>;
+    async fn get_category_by_id(&self, category_id: usize) -> Result<Option<Category>, String>;
+    /// Ids of the category and everything below it.
+    async fn get_category_subtree(&self, category_id: usize) -> Result<Vec<usize>, String>;
+    async fn update_category(
+        &self,
+        category_id: usize,
+        category: CategoryInput,
+    ) -> Result<Category, String>;
+    /// Returns `false` without deleting anything if the category has subcategories.
+    async fn delete_category(&self, category_id: usize) -> Result<bool, String>;
+
+    /// Most used tags starting with `prefix`.
+    async fn search_tags(&self, prefix: &str, limit: usize) -> Result<Vec<Tag>, String>;
 
-    async fn create_offer(&self, offer: OfferInput, user_id: usize) -> Result<Offer, String>;
+    async fn create_saved_search(
+        &self,
+        search: SavedSearchInput,
+        user_id: usize,
+    ) -> Result<SavedSearch, String>;
+    async fn get_saved_searches_by_user_id(
+        &self,
+        user_id: usize,
+    ) -> Result<Vec<SavedSearch>, String>;
+    async fn get_saved_search_by_id(&self, search_id: usize)
+        -> Result<Option<SavedSearch>, String>;
+    async fn delete_saved_search(&self, search_id: usize) -> Result<(), String>;
+    /// Notifies everyone with a saved search matching the order, once per
+    /// user, and returns how many were notified.
+    async fn notify_saved_searches(&self, order_id: usize) -> Result<usize, String>;
+
+    async fn create_template(
+        &self,
+        template: TemplateInput,
+        upload_ids: &[usize],
+        user_id: usize,
+    ) -> Result<OrderTemplate, String>;
+    async fn get_templates_by_user_id(&self, user_id: usize) -> Result<Vec<OrderTemplate>, String>;
+    async fn get_template_by_id(&self, template_id: usize)
+        -> Result<Option<OrderTemplate>, String>;
+    async fn delete_template(&self, template_id: usize) -> Result<(), String>;
+
+    async fn add_favorite(&self, user_id: usize, order_id: usize) -> Result<(), String>;
+    /// Returns `false` if the order wasn't a favorite.
+    async fn remove_favorite(&self, user_id: usize, order_id: usize) -> Result<bool, String>;
+    async fn get_favorite_orders(&self, user_id: usize) -> Result<Vec<Order>, String>;
+    /// Notifies everyone who favorited the order and returns how many were notified.
+    async fn notify_favorites(
+        &self,
+        order_id: usize,
+        kind: &str,
+        message: &str,
+    ) -> Result<usize, String>;
+    /// Notifies everyone with a pending or accepted offer on the order and
+    /// returns how many were notified.
+    async fn notify_bidders(
+        &self,
+        order_id: usize,
+        kind: &str,
+        message: &str,
+    ) -> Result<usize, String>;
+    /// Notifies, once per favorite, about open orders whose deadline is
+    /// less than `within` away.
+    async fn notify_expiring_favorites(&self, within: Duration) -> Result<usize, String>;
+
+    async fn notify_user(
+        &self,
+        user_id: usize,
+        kind: &str,
+        order_id: Option<usize>,
+        message: &str,
+    ) -> Result<(), String>;
+    async fn get_notifications(
+        &self,
+        user_id: usize,
+        unread_only: bool,
+    ) -> Result<Vec<Notification>, String>;
+    /// Marks the given notifications, or all of them if `None`, as read.
+    async fn mark_notifications_read(
+        &self,
+        user_id: usize,
+        notification_ids: Option<&[usize]>,
+    ) -> Result<(), String>;
+    /// Returns unread notifications still waiting for an email digest, of
+    /// users who haven't had one for at least `every`.
+    async fn get_due_digest_notifications(
+        &self,
+        every: Duration,
+    ) -> Result<Vec<Notification>, String>;
+    /// Records that a digest with `notification_ids` went out to `user_id`.
+    async fn mark_digest_sent(
+        &self,
+        user_id: usize,
+        notification_ids: &[usize],
+    ) -> Result<(), String>;
+
+    async fn create_upload(&self, upload: UploadInput) -> Result<Upload, String>;
+    async fn get_uploads_by_ids(&self, upload_ids: &[usize]) -> Result<Vec<Upload>, String>;
+    /// Returns `false` without deleting anything if an order or template still
+    /// uses the upload.
+    async fn delete_upload(&self, upload_id: usize) -> Result<bool, String>;
+    /// Deletes the given uploads no order or template uses anymore and returns them.
+    async fn delete_unused_uploads(&self, upload_ids: &[usize]) -> Result<Vec<Upload>, String>;
+
+    async fn add_order_images(&self, order_id: usize, upload_ids: &[usize]) -> Result<(), String>;
+    /// Returns the id of the removed image's upload, if the image was there.
+    async fn remove_order_image(
+        &self,
+        order_id: usize,
+        image_id: usize,
+    ) -> Result<Option<usize>, String>;
+    async fn reorder_order_images(
+        &self,
+        order_id: usize,
+        image_ids: &[usize],
+    ) -> Result<(), String>;
+
+    /// Adds attachments sent by one user all at once, unless they would take
+    /// the private files of the user past `quota` bytes in total, returning
+    /// `None` then.
+    async fn create_attachments(
+        &self,
+        attachments: Vec<AttachmentInput>,
+        quota: usize,
+    ) -> Result<Option<Vec<Attachment>>, String>;
+    async fn get_attachment_by_id(
+        &self,
+        attachment_id: usize,
+    ) -> Result<Option<Attachment>, String>;
+    async fn get_attachments_by_order_id(&self, order_id: usize)
+        -> Result<Vec<Attachment>, String>;
+    async fn delete_attachment(&self, attachment_id: usize) -> Result<(), String>;
+
+    /// `None` when the order isn't open or is the bidder's own.
+    async fn create_offer(
+        &self,
+        offer: OfferInput,
+        user_id: usize,
+    ) -> Result<Option<Offer>, String>;
     async fn get_offer_by_id(&self, offer_id: usize) -> Result<Option<Offer>, String>;
     async fn get_offers_by_user_id(&self, user_id: usize) -> Result<Vec<Offer>, String>;
     async fn get_offers_by_order_id(&self, order_id: usize) -> Result<Vec<Offer>, String>;
-    async fn update_offer_status(&self, offer_id: usize, status: &str) -> Result<Offer, String>;
+    async fn get_accepted_offer_by_order_id(
+        &self,
+        order_id: usize,
+    ) -> Result<Option<Offer>, String>;
+    /// Rejects a pending offer, `None` when it isn't pending anymore.
+    async fn reject_offer(&self, offer_id: usize) -> Result<Option<Offer>, String>;
+    /// Holds the price in escrow, accepts the offer, rejects the other
+    /// pending ones and assigns the order, all or nothing.
+    async fn accept_offer(&self, offer_id: usize) -> Result<AcceptOutcome, String>;
+    /// Same for a draft reserved for `user_id`, with an offer made for them.
+    /// Not open once the draft was published or the invite declined.
+    async fn accept_invite(&self, order_id: usize, user_id: usize)
+        -> Result<AcceptOutcome, String>;
+    /// Deletes an offer unless it was already accepted or rejected, the
+    /// escrow of an accepted one depends on it.
+    async fn withdraw_offer(&self, offer_id: usize) -> Result<bool, String>;
     async fn delete_offer(&self, offer_id: usize) -> Result<(), String>;
 
+    /// `None` when the offer isn't accepted or already has milestones.
+    async fn create_milestones(
+        &self,
+        offer_id: usize,
+        milestones: Vec<MilestoneInput>,
+    ) -> Result<Option<Vec<Milestone>>, String>;
+    async fn get_milestones_by_offer_id(&self, offer_id: usize) -> Result<Vec<Milestone>, String>;
+    async fn get_milestone_by_id(&self, milestone_id: usize) -> Result<Option<Milestone>, String>;
+    /// Approves a submitted milestone of an order in progress and pays its
+    /// amount out of escrow to the contractor. `None` when the milestone
+    /// isn't submitted or the order is no longer in progress.
+    async fn approve_milestone(&self, milestone_id: usize) -> Result<Option<Milestone>, String>;
+    /// Moves a milestone that is in one of the `from` states to `status`,
+    /// `None` when it's in none of them (anymore).
+    async fn update_milestone_status(
+        &self,
+        milestone_id: usize,
+        from: &[&str],
+        status: &str,
+        feedback: Option<&str>,
+    ) -> Result<Option<Milestone>, String>;
+
+    /// Holds the files to the same `quota` as attachments.
+    async fn create_delivery(
+        &self,
+        delivery: DeliveryInput,
+        quota: usize,
+    ) -> Result<DeliveryOutcome, String>;
+    async fn get_delivery_by_id(&self, delivery_id: usize) -> Result<Option<Delivery>, String>;
+    async fn get_deliveries_by_order_id(&self, order_id: usize) -> Result<Vec<Delivery>, String>;
+    /// Accepts a pending delivery, completes its order and pays the escrow
+    /// out to the contractor. `None` when the delivery is no longer pending
+    /// or the order no longer in progress.
+    async fn accept_delivery(&self, delivery_id: usize) -> Result<Option<Delivery>, String>;
+    /// Sends a pending delivery of an order in progress back to the
+    /// contractor, unless the order already had `max_revisions` of them.
+    async fn request_revision(
+        &self,
+        delivery_id: usize,
+        feedback: &str,
+        max_revisions: usize,
+    ) -> Result<RevisionOutcome, String>;
+
+    async fn get_balance(&self, user_id: usize) -> Result<Decimal, String>;
+    async fn get_transactions(&self, user_id: usize) -> Result<Vec<Transaction>, String>;
+    /// What was held in escrow when the order was assigned, `None` for orders
+    /// assigned before the ledger.
+    async fn get_escrow_hold(&self, order_id: usize) -> Result<Option<Decimal>, String>;
+
+    /// Opens a dispute on an order in progress and marks the order disputed,
+    /// all at once. The evidence is held to the same `quota` as attachments.
+    async fn create_dispute(
+        &self,
+        dispute: DisputeInput,
+        quota: usize,
+    ) -> Result<DisputeOutcome, String>;
+    async fn get_dispute_by_id(&self, dispute_id: usize) -> Result<Option<Dispute>, String>;
+    async fn get_open_disputes(&self) -> Result<Vec<Dispute>, String>;
+    /// `None` when the evidence would take `user_id` past `quota`.
+    async fn add_dispute_evidence(
+        &self,
+        dispute_id: usize,
+        user_id: usize,
+        evidence: &[PrivateFileInput],
+        quota: usize,
+    ) -> Result<Option<Dispute>, String>;
+    /// Settles the escrow of the disputed order as resolved, completes or
+    /// cancels the order and closes the dispute, all at once.
+    async fn resolve_dispute(
+        &self,
+        dispute_id: usize,
+        resolution: DisputeResolution,
+    ) -> Result<ResolveOutcome, String>;
+    async fn get_disputes_by_order_id(&self, order_id: usize) -> Result<Vec<Dispute>, String>;
+    async fn get_resolved_dispute_by_order_id(
+        &self,
+        order_id: usize,
+    ) -> Result<Option<Dispute>, String>;
+    async fn create_dispute_message(
+        &self,
+        dispute_id: usize,
+        sender_id: usize,
+        content: &str,
+    ) -> Result<DisputeMessage, String>;
+    async fn get_dispute_messages(&self, dispute_id: usize) -> Result<Vec<DisputeMessage>, String>;
+
     async fn create_message(&self, message: MessageInput) -> Result<Message, String>;
     async fn update_message(&self, message_id: usize, content: &str) -> Result<Message, String>;
     async fn get_message_by_id(&self, message_id: usize) -> Result<Option<Message>, String>;
@@ -50,6 +370,69 @@ pub trait Db {
         rating: u8,
     ) -> Result<Review, String>;
     async fn delete_review(&self, review_id: usize) -> Result<(), String>;
+
+    /// `None` if the reporter already has an open report on the target.
+    async fn create_report(&self, report: ReportInput) -> Result<Option<Report>, String>;
+    async fn get_report_by_id(&self, report_id: usize) -> Result<Option<Report>, String>;
+    /// Oldest first, so the queue is worked through in order.
+    async fn get_reports(&self, filter: &ReportFilter) -> Result<Vec<Report>, String>;
+    /// Logs the decision and closes every open report on its target.
+    async fn record_moderation_action(
+        &self,
+        action: ModerationActionInput,
+    ) -> Result<ModerationAction, String>;
+    /// Newest first.
+    async fn get_moderation_actions(
+        &self,
+        filter: &ModerationActionFilter,
+    ) -> Result<Vec<ModerationAction>, String>;
+    /// Takes an order, offer, message or review out of what others see.
+    async fn hide_content(&self, target_type: ReportTarget, target_id: usize)
+        -> Result<(), String>;
+
+    async fn create_suspension(&self, suspension: SuspensionInput) -> Result<Suspension, String>;
+    async fn get_suspension_by_id(
+        &self,
+        suspension_id: usize,
+    ) -> Result<Option<Suspension>, String>;
+    /// The suspension keeping the user out right now, the longest if there's
+    /// more than one.
+    async fn get_active_suspension(&self, user_id: usize) -> Result<Option<Suspension>, String>;
+    /// Newest first, lifted and expired ones included.
+    async fn get_suspensions_by_user_id(&self, user_id: usize) -> Result<Vec<Suspension>, String>;
+    /// `None` if the suspension isn't in force anymore.
+    async fn lift_suspension(
+        &self,
+        suspension_id: usize,
+        moderator_id: usize,
+    ) -> Result<Option<Suspension>, String>;
+    /// `None` if the suspension was already appealed.
+    async fn create_appeal(
+        &self,
+        suspension_id: usize,
+        message: &str,
+    ) -> Result<Option<Appeal>, String>;
+    async fn get_appeal_by_id(&self, appeal_id: usize) -> Result<Option<Appeal>, String>;
+    async fn get_appeal_by_suspension_id(
+        &self,
+        suspension_id: usize,
+    ) -> Result<Option<Appeal>, String>;
+    /// Oldest first, like the report queue.
+    async fn get_appeals(
+        &self,
+        status: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Appeal>, String>;
+    /// Decides on a pending appeal, lifting the suspension if it's accepted.
+    /// `None` if the appeal was already decided.
+    async fn review_appeal(
+        &self,
+        appeal_id: usize,
+        moderator_id: usize,
+        accepted: bool,
+        response: Option<&str>,
+    ) -> Result<Option<Appeal>, String>;
 }
 
 pub trait MsgListner {
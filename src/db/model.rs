@@ -1,5 +1,9 @@
+use std::str::FromStr;
+
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
 #[derive(Deserialize, Serialize)]
 pub struct User {
@@ -7,9 +11,21 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub password_hash: String,
+    pub role: String,
+    pub skills: Vec<String>,
     pub created_at: DateTime<Utc>,
 }
 
+impl User {
+    pub fn is_moderator(&self) -> bool {
+        self.role == "moderator" || self.role == "admin"
+    }
+
+    pub fn is_admin(&self) -> bool {
+        self.role == "admin"
+    }
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct UserInput {
     pub username: String,
@@ -23,8 +39,87 @@ pub struct Order {
     pub user_id: usize,
     pub order_name: String,
     pub order_desc: String,
-    pub price: f64,
-    pub image_urls: Vec<String>,
+    pub price: Decimal,
+    pub currency: String,
+    pub images: Vec<OrderImage>,
+    pub category_id: Option<usize>,
+    pub tags: Vec<String>,
+    pub deadline: Option<DateTime<Utc>>,
+    /// Can be done without meeting in person.
+    pub remote: bool,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub city: Option<String>,
+    pub school: Option<String>,
+    /// Distance from the point a listing was searched around.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>,
+    pub status: String,
+    /// When a scheduled draft goes public.
+    pub publish_at: Option<DateTime<Utc>>,
+    /// Contractor the draft is reserved for until then.
+    pub invited_user_id: Option<usize>,
+    /// Set by moderators, only the owner still sees the order.
+    pub hidden_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    /// Last time the order was changed after it was published.
+    pub edited_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+impl Order {
+    /// Fields that differ in `edited`, in the order they appear on orders.
+    pub fn changes(&self, edited: &Order) -> Vec<FieldChange> {
+        let fields = [
+            (
+                "order_name",
+                json!(self.order_name),
+                json!(edited.order_name),
+            ),
+            (
+                "order_desc",
+                json!(self.order_desc),
+                json!(edited.order_desc),
+            ),
+            ("price", json!(self.price), json!(edited.price)),
+            ("currency", json!(self.currency), json!(edited.currency)),
+            (
+                "category_id",
+                json!(self.category_id),
+                json!(edited.category_id),
+            ),
+            ("tags", json!(self.tags), json!(edited.tags)),
+            ("deadline", json!(self.deadline), json!(edited.deadline)),
+            ("remote", json!(self.remote), json!(edited.remote)),
+            ("latitude", json!(self.latitude), json!(edited.latitude)),
+            ("longitude", json!(self.longitude), json!(edited.longitude)),
+            ("city", json!(self.city), json!(edited.city)),
+            ("school", json!(self.school), json!(edited.school)),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, old, new)| old != new)
+            .map(|(field, old, new)| FieldChange {
+                field: field.to_string(),
+                old,
+                new,
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderRevision {
+    pub revision_id: usize,
+    pub order_id: usize,
+    pub changes: Vec<FieldChange>,
     pub created_at: DateTime<Utc>,
 }
 
@@ -32,8 +127,320 @@ pub struct Order {
 pub struct OrderInput {
     pub order_name: String,
     pub order_desc: String,
-    pub price: f64,
-    pub image_urls: Vec<String>,
+    pub price: Decimal,
+    pub currency: String,
+    pub category_id: Option<usize>,
+    pub tags: Vec<String>,
+    pub deadline: Option<DateTime<Utc>>,
+    pub remote: bool,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub city: Option<String>,
+    pub school: Option<String>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSort {
+    #[default]
+    Newest,
+    PriceAsc,
+    PriceDesc,
+    /// Closest deadline first, orders without one come last.
+    Deadline,
+    /// Closest first, only for listings searched around a point.
+    Distance,
+}
+
+/// Position in a listing, the sort key of the last order seen plus its id
+/// to break ties.
+#[derive(Deserialize, Serialize)]
+pub struct OrderCursor {
+    pub key: String,
+    pub order_id: usize,
+}
+
+/// Orders with coordinates within `radius_km` of a point.
+#[derive(Clone, Copy)]
+pub struct OrderNear {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub radius_km: f64,
+}
+
+#[derive(Default)]
+pub struct OrderFilter {
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub status: Option<String>,
+    /// Also matches orders in subcategories.
+    pub category_id: Option<usize>,
+    /// Orders have to carry all of these.
+    pub tags: Vec<String>,
+    pub user_id: Option<usize>,
+    pub remote: Option<bool>,
+    pub near: Option<OrderNear>,
+    pub sort: OrderSort,
+    pub after: Option<OrderCursor>,
+    pub limit: usize,
+}
+
+pub struct OrderSearch {
+    pub query: String,
+    pub category_id: Option<usize>,
+    pub tags: Vec<String>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderSearchHit {
+    #[serde(flatten)]
+    pub order: Order,
+    /// The order name, HTML-escaped, with matches wrapped in `<mark>`.
+    pub name_highlight: String,
+    /// Fragments of the description around the matches, marked the same way.
+    pub snippet: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderSearchResults {
+    pub total: usize,
+    pub hits: Vec<OrderSearchHit>,
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct Category {
+    pub category_id: usize,
+    pub parent_id: Option<usize>,
+    pub name: String,
+    pub slug: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CategoryInput {
+    pub parent_id: Option<usize>,
+    pub name: String,
+    pub slug: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Tag {
+    pub name: String,
+    pub order_count: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SavedSearch {
+    pub search_id: usize,
+    pub user_id: usize,
+    pub name: String,
+    pub query: String,
+    pub category_id: Option<usize>,
+    pub tags: Vec<String>,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    pub email_digest: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SavedSearchInput {
+    pub name: String,
+    pub query: String,
+    pub category_id: Option<usize>,
+    pub tags: Vec<String>,
+    pub min_price: Option<Decimal>,
+    pub max_price: Option<Decimal>,
+    pub email_digest: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Notification {
+    pub notification_id: usize,
+    pub user_id: usize,
+    pub kind: String,
+    pub order_id: Option<usize>,
+    pub message: String,
+    pub read_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderStats {
+    pub order_id: usize,
+    /// Distinct users and sessions that opened the order.
+    pub views: usize,
+    pub offers: usize,
+    /// Offers per view, `None` until someone has seen the order.
+    pub conversion_rate: Option<f64>,
+    pub seconds_to_first_offer: Option<i64>,
+}
+
+/// Totals over all published orders of a user.
+#[derive(Deserialize, Serialize)]
+pub struct UserOrderStats {
+    pub orders: usize,
+    pub views: usize,
+    pub offers: usize,
+    pub conversion_rate: Option<f64>,
+    /// Average over the orders that got an offer.
+    pub avg_seconds_to_first_offer: Option<i64>,
+}
+
+pub struct PastWork {
+    pub category_id: Option<usize>,
+    pub tags: Vec<String>,
+    pub completed: bool,
+}
+
+pub struct RecommendationCandidate {
+    pub order: Order,
+    /// Average rating the owner got, `None` without reviews.
+    pub owner_rating: Option<f64>,
+    pub owner_reviews: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RecommendedOrder {
+    #[serde(flatten)]
+    pub order: Order,
+    pub score: f64,
+    /// Why the order was picked, for showing next to it.
+    pub reasons: Vec<String>,
+}
+
+/// Recurrence of a series of orders, attached to its latest order.
+#[derive(Deserialize, Serialize)]
+pub struct OrderRecurrence {
+    pub order_id: usize,
+    pub rule: String,
+    pub starts_at: DateTime<Utc>,
+    /// When the latest order was due.
+    pub last_at: DateTime<Utc>,
+    pub next_at: DateTime<Utc>,
+    /// Orders in the series so far.
+    pub occurrences: usize,
+    pub auto_offer: bool,
+}
+
+/// Everything needed to post an order again, minus the deadline.
+#[derive(Deserialize, Serialize)]
+pub struct OrderTemplate {
+    pub template_id: usize,
+    pub user_id: usize,
+    pub name: String,
+    pub order_name: String,
+    pub order_desc: String,
+    pub price: Decimal,
+    pub currency: String,
+    pub category_id: Option<usize>,
+    pub tags: Vec<String>,
+    pub images: Vec<TemplateImage>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TemplateInput {
+    pub name: String,
+    pub order_name: String,
+    pub order_desc: String,
+    pub price: Decimal,
+    pub currency: String,
+    pub category_id: Option<usize>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TemplateImage {
+    pub upload_id: usize,
+    pub url: String,
+    pub medium_url: String,
+    pub thumbnail_url: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OrderImage {
+    pub image_id: usize,
+    pub upload_id: usize,
+    pub url: String,
+    pub medium_url: String,
+    pub thumbnail_url: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Upload {
+    pub upload_id: usize,
+    pub user_id: usize,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub medium_key: String,
+    pub medium_url: String,
+    #[serde(skip_serializing)]
+    pub thumbnail_key: String,
+    pub thumbnail_url: String,
+    pub content_type: String,
+    pub size: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct UploadInput {
+    pub user_id: usize,
+    pub storage_key: String,
+    pub url: String,
+    pub medium_key: String,
+    pub medium_url: String,
+    pub thumbnail_key: String,
+    pub thumbnail_url: String,
+    pub content_type: String,
+    pub size: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Attachment {
+    pub attachment_id: usize,
+    pub order_id: usize,
+    pub user_id: usize,
+    pub file_name: String,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub content_type: String,
+    pub size: usize,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct AttachmentInput {
+    pub order_id: usize,
+    pub user_id: usize,
+    pub file_name: String,
+    pub storage_key: String,
+    pub content_type: String,
+    pub size: usize,
+}
+
+/// A delivered file or piece of dispute evidence. These are kept in the
+/// private store and only handed out through signed links.
+#[derive(Deserialize, Serialize)]
+pub struct PrivateFile {
+    pub file_id: usize,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub content_type: String,
+    pub size: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct PrivateFileInput {
+    pub storage_key: String,
+    pub content_type: String,
+    pub size: usize,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -50,6 +457,155 @@ pub struct OfferInput {
     pub order_id: usize,
 }
 
+#[derive(Deserialize, Serialize)]
+pub struct Milestone {
+    pub milestone_id: usize,
+    pub offer_id: usize,
+    pub amount: Decimal,
+    pub due_date: DateTime<Utc>,
+    pub deliverable: String,
+    pub status: String,
+    pub feedback: Option<String>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct MilestoneInput {
+    pub amount: Decimal,
+    pub due_date: DateTime<Utc>,
+    pub deliverable: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Delivery {
+    pub delivery_id: usize,
+    pub order_id: usize,
+    pub user_id: usize,
+    pub note: String,
+    pub files: Vec<PrivateFile>,
+    pub status: String,
+    pub feedback: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DeliveryInput {
+    pub order_id: usize,
+    pub user_id: usize,
+    pub note: String,
+    pub files: Vec<PrivateFileInput>,
+}
+
+/// What came of submitting a delivery.
+pub enum DeliveryOutcome {
+    Created(Box<Delivery>),
+    /// The order isn't in progress or already has a delivery awaiting review.
+    NotAllowed,
+    /// The files would take the contractor past their storage quota.
+    OverQuota,
+}
+
+/// What came of asking for changes to a delivery.
+pub enum RevisionOutcome {
+    Requested(Box<Delivery>),
+    /// The delivery was accepted or sent back in the meantime.
+    NotPending,
+    /// The owner has to accept or dispute this one.
+    LimitReached,
+}
+
+/// What came of opening a dispute.
+pub enum DisputeOutcome {
+    Opened(Box<Dispute>),
+    /// The order isn't in progress anymore.
+    NotInProgress,
+    /// The evidence would take whoever opened it past their storage quota.
+    OverQuota,
+}
+
+/// What came of resolving a dispute.
+pub enum ResolveOutcome {
+    Resolved(Box<Dispute>),
+    /// Another moderator got to it first.
+    NotOpen,
+    /// The split asks for more than is left in escrow, some of it was
+    /// already paid out for milestones.
+    ExceedsEscrow,
+}
+
+/// What came of accepting an offer.
+pub enum AcceptOutcome {
+    Accepted(Offer),
+    /// The order isn't open for the offer anymore, someone else got it or it
+    /// was cancelled in the meantime.
+    NotOpen,
+    /// The owner can't cover the price.
+    InsufficientCredits,
+}
+
+/// What came of cancelling an order.
+pub enum CancelOutcome {
+    Cancelled(Box<Order>),
+    /// The order is past the point where it can be cancelled.
+    NotCancellable,
+    /// The contractor already delivered, so it has to go through a dispute.
+    Delivered,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Transaction {
+    pub entry_id: usize,
+    pub order_id: Option<usize>,
+    pub kind: String,
+    pub description: String,
+    pub amount: Decimal,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Dispute {
+    pub dispute_id: usize,
+    pub order_id: usize,
+    pub opened_by: usize,
+    pub reason: String,
+    pub evidence: Vec<PrivateFile>,
+    pub status: String,
+    pub resolution: Option<String>,
+    pub contractor_amount: Option<Decimal>,
+    pub resolution_note: Option<String>,
+    pub resolved_by: Option<usize>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DisputeInput {
+    pub order_id: usize,
+    pub opened_by: usize,
+    pub reason: String,
+    pub evidence: Vec<PrivateFileInput>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DisputeResolution {
+    pub resolution: String,
+    pub contractor_amount: Option<Decimal>,
+    pub note: String,
+    pub resolved_by: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DisputeMessage {
+    pub message_id: usize,
+    pub dispute_id: usize,
+    pub sender_id: usize,
+    pub content: String,
+    pub sent_at: DateTime<Utc>,
+}
+
 #[derive(Deserialize, Serialize, Clone)]
 pub struct Message {
     pub message_id: usize,
@@ -83,3 +639,184 @@ pub struct ReviewInput {
     pub rating: u8,
     pub content: String,
 }
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportTarget {
+    Order,
+    Offer,
+    Message,
+    Review,
+    User,
+}
+
+impl ReportTarget {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReportTarget::Order => "order",
+            ReportTarget::Offer => "offer",
+            ReportTarget::Message => "message",
+            ReportTarget::Review => "review",
+            ReportTarget::User => "user",
+        }
+    }
+}
+
+impl FromStr for ReportTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "order" => Ok(ReportTarget::Order),
+            "offer" => Ok(ReportTarget::Offer),
+            "message" => Ok(ReportTarget::Message),
+            "review" => Ok(ReportTarget::Review),
+            "user" => Ok(ReportTarget::User),
+            _ => Err(format!("Unknown report target {s}")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Report {
+    pub report_id: usize,
+    pub reporter_id: usize,
+    pub target_type: ReportTarget,
+    pub target_id: usize,
+    pub reason: String,
+    pub details: Option<String>,
+    pub status: String,
+    /// The decision that closed the report.
+    pub action_id: Option<usize>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ReportInput {
+    pub reporter_id: usize,
+    pub target_type: ReportTarget,
+    pub target_id: usize,
+    pub reason: String,
+    pub details: Option<String>,
+}
+
+pub struct ReportFilter {
+    pub status: String,
+    pub target_type: Option<ReportTarget>,
+    pub reason: Option<String>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModerationDecision {
+    /// Close the reports without doing anything.
+    Dismiss,
+    /// Take the content out of listings but keep it for its author.
+    Hide,
+    Delete,
+    /// Send the author a warning.
+    Warn,
+    /// Block the author from signing in for a while.
+    Suspend,
+    /// Block the author from signing in for good.
+    Ban,
+}
+
+impl ModerationDecision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ModerationDecision::Dismiss => "dismiss",
+            ModerationDecision::Hide => "hide",
+            ModerationDecision::Delete => "delete",
+            ModerationDecision::Warn => "warn",
+            ModerationDecision::Suspend => "suspend",
+            ModerationDecision::Ban => "ban",
+        }
+    }
+}
+
+impl FromStr for ModerationDecision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dismiss" => Ok(ModerationDecision::Dismiss),
+            "hide" => Ok(ModerationDecision::Hide),
+            "delete" => Ok(ModerationDecision::Delete),
+            "warn" => Ok(ModerationDecision::Warn),
+            "suspend" => Ok(ModerationDecision::Suspend),
+            "ban" => Ok(ModerationDecision::Ban),
+            _ => Err(format!("Unknown moderation action {s}")),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ModerationAction {
+    pub action_id: usize,
+    pub moderator_id: Option<usize>,
+    pub action: ModerationDecision,
+    pub target_type: ReportTarget,
+    pub target_id: usize,
+    pub target_user_id: Option<usize>,
+    /// The target as it was when the decision was made.
+    pub snapshot: Value,
+    pub note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ModerationActionInput {
+    pub moderator_id: usize,
+    pub action: ModerationDecision,
+    pub target_type: ReportTarget,
+    pub target_id: usize,
+    pub target_user_id: Option<usize>,
+    pub snapshot: Value,
+    pub note: Option<String>,
+}
+
+#[derive(Default)]
+pub struct ModerationActionFilter {
+    pub target_type: Option<ReportTarget>,
+    pub target_id: Option<usize>,
+    pub moderator_id: Option<usize>,
+    pub limit: usize,
+    pub offset: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Suspension {
+    pub suspension_id: usize,
+    pub user_id: usize,
+    pub moderator_id: Option<usize>,
+    pub reason: String,
+    /// `None` for a permanent ban.
+    pub ends_at: Option<DateTime<Utc>>,
+    pub lifted_at: Option<DateTime<Utc>>,
+    pub lifted_by: Option<usize>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SuspensionInput {
+    pub user_id: usize,
+    pub moderator_id: usize,
+    pub reason: String,
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct Appeal {
+    pub appeal_id: usize,
+    pub suspension_id: usize,
+    pub message: String,
+    pub status: String,
+    /// The moderator's answer, if they gave one.
+    pub response: Option<String>,
+    pub reviewed_by: Option<usize>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,67 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+    db::{postgres::PostgresDb, Db, Notification},
+    mail::{escape_html, Mailer},
+};
+
+/// How often to look for users who are due a digest.
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// How long users go between two digests.
+const DIGEST_INTERVAL: chrono::Duration = chrono::Duration::hours(24);
+
+/// Periodically emails every user their notifications they haven't read yet,
+/// at most once per `DIGEST_INTERVAL`.
+pub async fn run(db: PostgresDb, mailer: Mailer) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = send_digests(&db, &mailer).await {
+            eprintln!("Could not send email digests: {e}");
+        }
+    }
+}
+
+/// Notifications are only marked as sent once their email went out, a failed
+/// one is tried again on the next check.
+async fn send_digests(db: &PostgresDb, mailer: &Mailer) -> Result<(), String> {
+    let mut by_user = BTreeMap::<usize, Vec<Notification>>::new();
+    for notification in db.get_due_digest_notifications(DIGEST_INTERVAL).await? {
+        by_user
+            .entry(notification.user_id)
+            .or_default()
+            .push(notification);
+    }
+
+    for (user_id, notifications) in by_user {
+        let Some(user) = db.get_user_by_id(user_id).await? else {
+            continue;
+        };
+        let items = notifications
+            .iter()
+            .map(|notification| format!("<li>{}</li>", escape_html(&notification.message)))
+            .collect::<String>();
+        let sent = mailer
+            .try_send(
+                &user.email,
+                "Nowe zlecenia dla ciebie",
+                format!(
+                    "<p>Czesc {}, oto co sie wydarzylo od ostatniej wiadomosci:</p><ul>{}</ul>",
+                    escape_html(&user.username),
+                    items
+                ),
+            )
+            .await;
+        if let Err(e) = sent {
+            eprintln!("Could not send the digest to user {user_id}: {e}");
+            continue;
+        }
+        let ids = notifications
+            .iter()
+            .map(|notification| notification.notification_id)
+            .collect::<Vec<_>>();
+        db.mark_digest_sent(user_id, &ids).await?;
+    }
+    Ok(())
+}
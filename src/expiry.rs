@@ -0,0 +1,19 @@
+use std::time::Duration;
+
+use crate::db::{postgres::PostgresDb, Db};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// How long before the deadline users are reminded about their favorites.
+const REMINDER_WINDOW: chrono::Duration = chrono::Duration::hours(24);
+
+/// Periodically reminds users about favorited orders that are about to expire.
+pub async fn run(db: PostgresDb) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(e) = db.notify_expiring_favorites(REMINDER_WINDOW).await {
+            eprintln!("Could not send expiry reminders: {e}");
+        }
+    }
+}
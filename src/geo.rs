@@ -0,0 +1,100 @@
+/// Mean radius of the Earth, which is close enough to a sphere for finding
+/// orders nearby.
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Length of a degree of latitude.
+pub const KM_PER_DEGREE: f64 = 111.2;
+
+pub const MAX_RADIUS_KM: f64 = 1000.0;
+
+/// Place names are stored as `VARCHAR(100)`.
+const MAX_PLACE_LENGTH: usize = 100;
+
+/// Checks that coordinates are given together and lie on the map.
+pub fn validate_coordinates(latitude: Option<f64>, longitude: Option<f64>) -> Result<(), String> {
+    match (latitude, longitude) {
+        (None, None) => Ok(()),
+        (Some(latitude), Some(longitude)) => {
+            if !(-90.0..=90.0).contains(&latitude) {
+                return Err("Latitude must be between -90 and 90".to_string());
+            }
+            if !(-180.0..=180.0).contains(&longitude) {
+                return Err("Longitude must be between -180 and 180".to_string());
+            }
+            Ok(())
+        }
+        _ => Err("Latitude and longitude go together".to_string()),
+    }
+}
+
+pub fn validate_radius(radius_km: f64) -> Result<(), String> {
+    if radius_km > 0.0 && radius_km <= MAX_RADIUS_KM {
+        Ok(())
+    } else {
+        Err(format!(
+            "Radius must be above 0 and at most {MAX_RADIUS_KM} km"
+        ))
+    }
+}
+
+/// Trims a city or school name, dropping it when blank.
+pub fn normalize_place(place: Option<String>) -> Result<Option<String>, String> {
+    let Some(place) = place.map(|place| place.trim().to_string()) else {
+        return Ok(None);
+    };
+    if place.is_empty() {
+        return Ok(None);
+    }
+    if place.chars().count() > MAX_PLACE_LENGTH {
+        return Err(format!(
+            "City and school can have at most {MAX_PLACE_LENGTH} characters"
+        ));
+    }
+    Ok(Some(place))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coordinates_go_together() {
+        assert!(validate_coordinates(None, None).is_ok());
+        assert!(validate_coordinates(Some(52.23), Some(21.01)).is_ok());
+        assert!(validate_coordinates(Some(52.23), None).is_err());
+        assert!(validate_coordinates(None, Some(21.01)).is_err());
+    }
+
+    #[test]
+    fn coordinates_lie_on_the_map() {
+        assert!(validate_coordinates(Some(-90.0), Some(180.0)).is_ok());
+        assert!(validate_coordinates(Some(90.0), Some(-180.0)).is_ok());
+        assert!(validate_coordinates(Some(90.1), Some(0.0)).is_err());
+        assert!(validate_coordinates(Some(0.0), Some(-180.1)).is_err());
+        assert!(validate_coordinates(Some(f64::NAN), Some(0.0)).is_err());
+        assert!(validate_coordinates(Some(0.0), Some(f64::INFINITY)).is_err());
+    }
+
+    #[test]
+    fn radius_is_bounded() {
+        assert!(validate_radius(0.5).is_ok());
+        assert!(validate_radius(MAX_RADIUS_KM).is_ok());
+        assert!(validate_radius(0.0).is_err());
+        assert!(validate_radius(-5.0).is_err());
+        assert!(validate_radius(MAX_RADIUS_KM + 0.1).is_err());
+        assert!(validate_radius(f64::NAN).is_err());
+    }
+
+    #[test]
+    fn places_are_trimmed() {
+        assert_eq!(normalize_place(None), Ok(None));
+        assert_eq!(normalize_place(Some("  ".to_string())), Ok(None));
+        assert_eq!(
+            normalize_place(Some(" Kraków ".to_string())),
+            Ok(Some("Kraków".to_string()))
+        );
+        let longest = "ż".repeat(MAX_PLACE_LENGTH);
+        assert_eq!(normalize_place(Some(longest.clone())), Ok(Some(longest)));
+        assert!(normalize_place(Some("a".repeat(MAX_PLACE_LENGTH + 1))).is_err());
+    }
+}
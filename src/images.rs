@@ -0,0 +1,73 @@
+use std::io::Cursor;
+
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, ImageDecoder, ImageFormat,
+    ImageReader, Limits,
+};
+
+const FULL_SIZE: u32 = 2048;
+const MEDIUM_SIZE: u32 = 1024;
+const THUMBNAIL_SIZE: u32 = 256;
+const JPEG_QUALITY: u8 = 85;
+
+pub struct ProcessedImage {
+    pub full: Vec<u8>,
+    pub medium: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+    pub content_type: &'static str,
+}
+
+/// Decodes an uploaded image and re-encodes it in three sizes.
+///
+/// Decoding the whole image is what proves the upload really is one, and
+/// since only the pixels are re-encoded, EXIF data (GPS position included)
+/// never makes it into the stored files. The EXIF orientation is applied to
+/// the pixels first so photos don't end up sideways.
+pub fn process_image(data: &[u8]) -> Result<ProcessedImage, String> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(12_000);
+    limits.max_image_height = Some(12_000);
+    limits.max_alloc = Some(512 * 1024 * 1024);
+
+    let mut reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .map_err(|e| e.to_string())?;
+    reader.limits(limits);
+    let mut decoder = reader.into_decoder().map_err(|e| e.to_string())?;
+    let orientation = decoder.orientation().map_err(|e| e.to_string())?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(|e| e.to_string())?;
+    image.apply_orientation(orientation);
+
+    // Keep transparency where there is some, everything else becomes a JPEG
+    let content_type = if image.color().has_alpha() {
+        "image/png"
+    } else {
+        "image/jpeg"
+    };
+    let encode = |size: u32| -> Result<Vec<u8>, String> {
+        let resized = if image.width() > size || image.height() > size {
+            image.resize(size, size, FilterType::Lanczos3)
+        } else {
+            image.clone()
+        };
+        let mut out = Vec::new();
+        if image.color().has_alpha() {
+            resized
+                .write_to(&mut Cursor::new(&mut out), ImageFormat::Png)
+                .map_err(|e| e.to_string())?;
+        } else {
+            resized
+                .to_rgb8()
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut out, JPEG_QUALITY))
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(out)
+    };
+
+    Ok(ProcessedImage {
+        full: encode(FULL_SIZE)?,
+        medium: encode(MEDIUM_SIZE)?,
+        thumbnail: encode(THUMBNAIL_SIZE)?,
+        content_type,
+    })
+}
@@ -0,0 +1,85 @@
+use lettre::message::{Mailbox, Message, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{SmtpTransport, Transport};
+use shuttle_runtime::SecretStore;
+
+/// Sends emails through Gmail with the account from the `SMTP_USERNAME` and
+/// `SMTP_PASSWORD` secrets (an app password). Without them emails are only
+/// logged, so the app still runs locally.
+#[derive(Clone)]
+pub struct Mailer {
+    credentials: Option<Credentials>,
+}
+
+impl Mailer {
+    pub fn from_secrets(secrets: &SecretStore) -> Self {
+        Self {
+            credentials: secrets
+                .get("SMTP_USERNAME")
+                .zip(secrets.get("SMTP_PASSWORD"))
+                .map(|(username, password)| Credentials::new(username, password)),
+        }
+    }
+
+    /// Sends an HTML email, logging instead of failing if it can't be delivered.
+    pub async fn send(&self, recipient: &str, subject: &str, body: String) {
+        if let Err(e) = self.try_send(recipient, subject, body).await {
+            eprintln!("Could not send email to {recipient}: {e}");
+        }
+    }
+
+    /// Sends an HTML email, telling whether it went out. Without credentials
+    /// it is only logged, which counts as sent.
+    pub async fn try_send(
+        &self,
+        recipient: &str,
+        subject: &str,
+        body: String,
+    ) -> Result<(), String> {
+        let Some(creds) = self.credentials.clone() else {
+            println!("Email is not configured, not sending {subject:?} to {recipient}");
+            return Ok(());
+        };
+        let recipient = recipient.parse::<Mailbox>().map_err(|e| e.to_string())?;
+
+        // Build the email
+        let email = Message::builder()
+            .from(
+                "techni zamowienia <technizamowienia@gmail.com>"
+                    .parse()
+                    .unwrap(),
+            )
+            .to(recipient)
+            .subject(subject)
+            .singlepart(
+                SinglePart::builder()
+                    .header(lettre::message::header::ContentType::TEXT_HTML)
+                    .body(body),
+            )
+            .unwrap();
+
+        // Open a remote connection to Gmail
+        let mailer = SmtpTransport::relay("smtp.gmail.com")
+            .unwrap()
+            .credentials(creds)
+            .build();
+
+        // Send the email, the transport blocks so keep it off the async workers
+        match tokio::task::spawn_blocking(move || mailer.send(&email)).await {
+            Ok(Ok(_)) => {
+                println!("Email sent successfully!");
+                Ok(())
+            }
+            Ok(Err(e)) => Err(format!("{:?}", e)),
+            Err(e) => Err(format!("{:?}", e)),
+        }
+    }
+}
+
+/// Escapes user provided text for use in an email body.
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -8,17 +8,41 @@ use tower_http::cors::CorsLayer;
 use crate::{
     auth::init_keys,
     db::{postgres::PostgresDb, Db},
-    routes::{messages, offers, orders, reviews, user},
+    mail::Mailer,
+    routes::{
+        attachments, categories, credits, deliveries, disputes, favorites, messages, milestones,
+        notifications, offers, order_images, orders, recommendations, recurrences, reports,
+        reviews, revisions, saved_searches, stats, suspensions, tags, templates, uploads, user,
+    },
+    scanner::Scanner,
+    storage::Storage,
 };
 
 mod auth;
 mod chat;
 mod db;
+mod digest;
+mod expiry;
+mod geo;
+mod images;
+mod mail;
+mod money;
+mod publisher;
+mod receipt;
+mod recommend;
+mod recurrence;
 mod routes;
+mod scanner;
+mod storage;
 
 #[derive(Clone)]
 struct AppState<T: Db> {
     pub db: T,
+    pub store: Storage,
+    /// Never served directly, see [`Storage::private_from_secrets`].
+    pub private_store: Storage,
+    pub scanner: Scanner,
+    pub mailer: Mailer,
 }
 
 #[shuttle_runtime::main]
@@ -28,15 +52,53 @@ async fn main(
 ) -> shuttle_axum::ShuttleAxum {
     init_keys(secrets.get("JWT_SECRET").unwrap().as_bytes());
 
-    let router = Router::new()
+    let store = Storage::from_secrets(&secrets).unwrap();
+    let private_store = Storage::private_from_secrets(&secrets).unwrap();
+    let scanner = Scanner::from_secrets(&secrets);
+    let mailer = Mailer::from_secrets(&secrets);
+
+    let db = PostgresDb::new(pool).await;
+    tokio::spawn(digest::run(db.clone(), mailer.clone()));
+    tokio::spawn(expiry::run(db.clone()));
+    tokio::spawn(publisher::run(db.clone()));
+    tokio::spawn(recurrence::run(db.clone()));
+
+    let mut router = Router::new()
+        .merge(attachments::router())
+        .merge(categories::router())
+        .merge(credits::router())
+        .merge(deliveries::router())
+        .merge(disputes::router())
+        .merge(favorites::router())
         .merge(messages::router())
+        .merge(milestones::router())
+        .merge(notifications::router())
         .merge(offers::router())
+        .merge(order_images::router())
         .merge(orders::router())
+        .merge(recommendations::router())
+        .merge(recurrences::router())
+        .merge(reports::router())
         .merge(reviews::router())
-        .nest("/user", user::router())
+        .merge(revisions::router())
+        .merge(saved_searches::router())
+        .merge(stats::router())
+        .merge(suspensions::router())
+        .merge(tags::router())
+        .merge(templates::router())
+        .merge(uploads::router())
+        .nest("/user", user::router());
+    if let Storage::Local(local) = &store {
+        router = router.nest_service("/files", local.router());
+    }
+    let router = router
         .layer(CorsLayer::permissive().max_age(Duration::from_secs(60) * 60))
         .with_state(AppState {
-            db: PostgresDb::new(pool).await,
+            db,
+            store,
+            private_store,
+            scanner,
+            mailer,
         });
 
     Ok(router.into())
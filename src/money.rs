@@ -0,0 +1,93 @@
+use rust_decimal::Decimal;
+
+/// Currency credits are kept in, and the one orders are priced in by default.
+pub const DEFAULT_CURRENCY: &str = "PLN";
+
+/// Escrow moves order prices through the credit ledger as they are, so orders
+/// can only be priced in currencies the ledger is kept in.
+const CURRENCIES: &[&str] = &[DEFAULT_CURRENCY];
+
+/// Money is stored as `NUMERIC(10, 2)`.
+const MAX_SCALE: u32 = 2;
+const MAX_DIGITS: u32 = 10;
+
+pub fn default_currency() -> String {
+    DEFAULT_CURRENCY.to_string()
+}
+
+/// Checks that an amount fits the money columns, so it's rejected with a
+/// readable message instead of a database error.
+pub fn validate_amount(amount: Decimal) -> Result<(), String> {
+    if amount.is_sign_negative() && !amount.is_zero() {
+        return Err("Amounts can't be negative".to_string());
+    }
+    if amount.normalize().scale() > MAX_SCALE {
+        return Err(format!(
+            "Amounts can have at most {MAX_SCALE} decimal places"
+        ));
+    }
+    if amount.trunc() >= Decimal::from(10i64.pow(MAX_DIGITS - MAX_SCALE)) {
+        return Err(format!(
+            "Amounts can have at most {} digits before the decimal point",
+            MAX_DIGITS - MAX_SCALE
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_currency(currency: &str) -> Result<(), String> {
+    if CURRENCIES.contains(&currency) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Unsupported currency, use one of: {}",
+            CURRENCIES.join(", ")
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn amount(amount: &str) -> Decimal {
+        Decimal::from_str(amount).unwrap()
+    }
+
+    #[test]
+    fn accepts_amounts_that_fit() {
+        for valid in ["0", "-0", "10", "10.5", "10.50", "10.500", "99999999.99"] {
+            assert!(
+                validate_amount(amount(valid)).is_ok(),
+                "{valid} was rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_negative_amounts() {
+        assert!(validate_amount(amount("-0.01")).is_err());
+        assert!(validate_amount(amount("-10")).is_err());
+    }
+
+    #[test]
+    fn rejects_more_than_two_decimal_places() {
+        assert!(validate_amount(amount("1.005")).is_err());
+        assert!(validate_amount(amount("0.001")).is_err());
+    }
+
+    #[test]
+    fn rejects_amounts_too_large_for_the_column() {
+        assert!(validate_amount(amount("100000000")).is_err());
+        assert!(validate_amount(amount("100000000.00")).is_err());
+    }
+
+    #[test]
+    fn only_ledger_currencies_are_supported() {
+        assert!(validate_currency(DEFAULT_CURRENCY).is_ok());
+        assert!(validate_currency("EUR").is_err());
+        assert!(validate_currency("pln").is_err());
+    }
+}
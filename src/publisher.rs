@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use crate::db::{postgres::PostgresDb, Db};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Publishes scheduled drafts once their `publish_at` has passed.
+pub async fn run(db: PostgresDb) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let order_ids = match db.publish_due_orders().await {
+            Ok(order_ids) => order_ids,
+            Err(e) => {
+                eprintln!("Could not publish scheduled orders: {e}");
+                continue;
+            }
+        };
+        for order_id in order_ids {
+            if let Err(e) = db.notify_saved_searches(order_id).await {
+                eprintln!("Could not match saved searches: {e}");
+            }
+        }
+    }
+}
@@ -0,0 +1,318 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::db::{Dispute, Milestone, Offer, Order, User};
+
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 56;
+const LINE_HEIGHT: u32 = 16;
+const WRAP_AT: usize = 90;
+
+enum Style {
+    Title,
+    Heading,
+    Body,
+}
+
+struct Line {
+    style: Style,
+    text: String,
+}
+
+/// Renders a one-or-more page PDF receipt for a completed order. `agreed_price`
+/// is what was held in escrow for it and `settlement` the dispute the order
+/// was completed through, if any.
+///
+/// The document only uses the standard Helvetica fonts, so nothing has to be
+/// embedded and it can be produced without any external service.
+pub fn render_receipt(
+    order: &Order,
+    agreed_price: Decimal,
+    owner: &User,
+    contractor: &User,
+    offer: &Offer,
+    milestones: &[Milestone],
+    settlement: Option<&Dispute>,
+) -> Vec<u8> {
+    let mut lines = Vec::new();
+    let mut push = |style, text: String| lines.push(Line { style, text });
+
+    push(
+        Style::Title,
+        format!("Receipt for order #{}", order.order_id),
+    );
+    push(Style::Body, String::new());
+    push(Style::Heading, "Order".to_string());
+    push(Style::Body, format!("Title: {}", order.order_name));
+    push(
+        Style::Body,
+        format!("Agreed price: {:.2} {}", agreed_price, order.currency),
+    );
+    if let Some(Dispute {
+        dispute_id,
+        contractor_amount: Some(amount),
+        ..
+    }) = settlement
+    {
+        push(
+            Style::Body,
+            format!(
+                "Paid after dispute #{}: {:.2} {}, the rest was refunded",
+                dispute_id, amount, order.currency
+            ),
+        );
+    }
+    push(
+        Style::Body,
+        format!("Posted: {}", format_date(order.created_at)),
+    );
+    push(
+        Style::Body,
+        format!("Offer placed: {}", format_date(offer.created_at)),
+    );
+    if let Some(completed_at) = order.completed_at {
+        push(
+            Style::Body,
+            format!("Completed: {}", format_date(completed_at)),
+        );
+    }
+    push(Style::Body, String::new());
+    push(Style::Heading, "Parties".to_string());
+    push(
+        Style::Body,
+        format!("Ordered by: {} <{}>", owner.username, owner.email),
+    );
+    push(
+        Style::Body,
+        format!(
+            "Delivered by: {} <{}>",
+            contractor.username, contractor.email
+        ),
+    );
+    push(Style::Body, String::new());
+    push(Style::Heading, "Description".to_string());
+    for paragraph in order.order_desc.lines() {
+        for line in wrap(paragraph) {
+            push(Style::Body, line);
+        }
+    }
+    if !milestones.is_empty() {
+        push(Style::Body, String::new());
+        push(Style::Heading, "Milestones".to_string());
+        for (i, milestone) in milestones.iter().enumerate() {
+            push(
+                Style::Body,
+                format!(
+                    "{}. {:.2} {} due {} ({})",
+                    i + 1,
+                    milestone.amount,
+                    order.currency,
+                    format_date(milestone.due_date),
+                    milestone.status
+                ),
+            );
+            for line in wrap(&milestone.deliverable) {
+                push(Style::Body, format!("    {}", line));
+            }
+        }
+    }
+
+    render_pdf(&lines)
+}
+
+fn format_date(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+fn wrap(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in text.split_whitespace() {
+        if !current.is_empty() && current.chars().count() + word.chars().count() >= WRAP_AT {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Encodes `text` as a PDF string literal in WinAnsiEncoding.
+///
+/// Polish letters outside of that encoding are written without their
+/// diacritics, anything else that doesn't fit becomes `?`.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in text.chars() {
+        let c = match c {
+            'ą' => 'a',
+            'ć' => 'c',
+            'ę' => 'e',
+            'ł' => 'l',
+            'ń' => 'n',
+            'ś' => 's',
+            'ź' | 'ż' => 'z',
+            'Ą' => 'A',
+            'Ć' => 'C',
+            'Ę' => 'E',
+            'Ł' => 'L',
+            'Ń' => 'N',
+            'Ś' => 'S',
+            'Ź' | 'Ż' => 'Z',
+            c => c,
+        };
+        match c {
+            '(' | ')' | '\\' => out.extend([b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => out.push(c as u8),
+            _ => out.push(b'?'),
+        }
+    }
+    out.push(b')');
+    out
+}
+
+fn render_pdf(lines: &[Line]) -> Vec<u8> {
+    let per_page = ((PAGE_HEIGHT - 2 * MARGIN) / LINE_HEIGHT) as usize;
+    let pages = lines.chunks(per_page).collect::<Vec<_>>();
+
+    // Objects 1-4 are the catalog, page tree and fonts, followed by a
+    // page and content stream object for every page
+    let mut objects = Vec::<Vec<u8>>::new();
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", 5 + 2 * i))
+        .collect::<Vec<_>>()
+        .join(" ");
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects
+        .push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).into_bytes());
+    objects.push(
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    );
+    objects.push(
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+    );
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                6 + 2 * i
+            )
+            .into_bytes(),
+        );
+
+        let mut content = Vec::new();
+        let mut y = PAGE_HEIGHT - MARGIN;
+        for line in page.iter() {
+            let (font, size) = match line.style {
+                Style::Title => ("F2", 18),
+                Style::Heading => ("F2", 13),
+                Style::Body => ("F1", 11),
+            };
+            content.extend(format!("BT /{} {} Tf {} {} Td ", font, size, MARGIN, y).bytes());
+            content.extend(pdf_string(&line.text));
+            content.extend(b" Tj ET\n");
+            y -= LINE_HEIGHT;
+        }
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend(content);
+        stream.extend(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).bytes());
+        pdf.extend(object);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .bytes(),
+    );
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_keeps_short_text_on_one_line() {
+        assert_eq!(wrap("Fix the  login\tform"), ["Fix the login form"]);
+        assert_eq!(wrap(""), [""]);
+    }
+
+    #[test]
+    fn wrap_breaks_between_words() {
+        let text = "lorem ipsum dolor ".repeat(20);
+        let lines = wrap(&text);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.chars().count() < WRAP_AT));
+        assert_eq!(lines.join(" "), text.trim_end());
+    }
+
+    #[test]
+    fn wrap_gives_long_words_their_own_line() {
+        let word = "x".repeat(WRAP_AT * 2);
+        assert_eq!(wrap(&format!("a {word} b")), ["a", word.as_str(), "b"]);
+    }
+
+    #[test]
+    fn pdf_string_escapes_delimiters() {
+        assert_eq!(pdf_string(r"f(x) \ 2"), br"(f\(x\) \\ 2)");
+    }
+
+    #[test]
+    fn pdf_string_encodes_win_ansi() {
+        assert_eq!(pdf_string("Zażółć gęślą jaźń"), b"(Zaz\xf3lc gesla jazn)");
+        assert_eq!(pdf_string("£5 or 5\u{20ac} 😀"), b"(\xa35 or 5? ?)");
+    }
+
+    #[test]
+    fn render_pdf_splits_pages_and_indexes_objects() {
+        let lines = (0..100)
+            .map(|i| Line {
+                style: Style::Body,
+                text: format!("Line {i}"),
+            })
+            .collect::<Vec<_>>();
+        let text = String::from_utf8(render_pdf(&lines)).unwrap();
+        assert!(text.contains("/Count 3"));
+
+        // Every xref entry has to point at the object it numbers
+        let xref = text.rsplit("startxref\n").next().unwrap();
+        let xref = xref.lines().next().unwrap().parse::<usize>().unwrap();
+        let entries = text[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|line| line.ends_with(" n "))
+            .collect::<Vec<_>>();
+        // Catalog, page tree, two fonts and a page and its content per page
+        assert_eq!(entries.len(), 4 + 2 * 3);
+        for (i, entry) in entries.iter().enumerate() {
+            let offset = entry[..10].parse::<usize>().unwrap();
+            assert!(text[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
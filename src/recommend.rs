@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::db::{PastWork, RecommendationCandidate, RecommendedOrder};
+
+/// A skill the order is tagged with.
+const SKILL_TAG_WEIGHT: f64 = 3.0;
+/// A skill mentioned in the order name or description.
+const SKILL_TEXT_WEIGHT: f64 = 1.5;
+/// Tags shared with orders the contractor was accepted for, growing with the
+/// log of how many.
+const PAST_TAG_WEIGHT: f64 = 1.0;
+/// Same for the category, with completed orders counting twice.
+const PAST_CATEGORY_WEIGHT: f64 = 2.0;
+/// Owners rated 5 get this much, 3 nothing and 1 as much taken away.
+const RATING_WEIGHT: f64 = 1.0;
+/// Reviews until an owner's rating is fully trusted.
+const TRUSTED_REVIEWS: usize = 5;
+/// New orders get this much, halving every `FRESHNESS_HALF_LIFE_DAYS`.
+const FRESHNESS_WEIGHT: f64 = 1.0;
+const FRESHNESS_HALF_LIFE_DAYS: f64 = 3.0;
+
+/// Ranks `candidates` for a contractor with `skills` who did `past_work`,
+/// best first.
+pub fn rank(
+    skills: &[String],
+    past_work: &[PastWork],
+    candidates: Vec<RecommendationCandidate>,
+    limit: usize,
+) -> Vec<RecommendedOrder> {
+    let mut past_tags = HashMap::<&str, usize>::new();
+    let mut past_categories = HashMap::<usize, usize>::new();
+    for work in past_work {
+        for tag in &work.tags {
+            *past_tags.entry(tag).or_default() += 1;
+        }
+        if let Some(category_id) = work.category_id {
+            *past_categories.entry(category_id).or_default() += if work.completed { 2 } else { 1 };
+        }
+    }
+
+    let mut ranked = candidates
+        .into_iter()
+        .map(|candidate| {
+            let (score, reasons) = score(skills, &past_tags, &past_categories, &candidate);
+            RecommendedOrder {
+                order: candidate.order,
+                score,
+                reasons,
+            }
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(b.order.order_id.cmp(&a.order.order_id))
+    });
+    ranked.truncate(limit);
+    ranked
+}
+
+fn score(
+    skills: &[String],
+    past_tags: &HashMap<&str, usize>,
+    past_categories: &HashMap<usize, usize>,
+    candidate: &RecommendationCandidate,
+) -> (f64, Vec<String>) {
+    let order = &candidate.order;
+    let mut score = 0.0;
+    let mut reasons = Vec::new();
+
+    let text = words(&format!("{} {}", order.order_name, order.order_desc));
+    for skill in skills {
+        if order.tags.contains(skill) {
+            score += SKILL_TAG_WEIGHT;
+            reasons.push(format!("Tagged with your skill \"{skill}\""));
+        } else if text.contains(&words(skill)) {
+            score += SKILL_TEXT_WEIGHT;
+            reasons.push(format!("Mentions your skill \"{skill}\""));
+        }
+    }
+
+    let shared = order
+        .tags
+        .iter()
+        .filter_map(|tag| past_tags.get(tag.as_str()))
+        .map(|&count| (1.0 + count as f64).ln())
+        .sum::<f64>();
+    if shared > 0.0 {
+        score += PAST_TAG_WEIGHT * shared;
+        reasons.push("Similar to orders you were accepted for".to_string());
+    }
+    if let Some(&count) = order
+        .category_id
+        .and_then(|category_id| past_categories.get(&category_id))
+    {
+        score += PAST_CATEGORY_WEIGHT * (1.0 + count as f64).ln();
+        reasons.push("In a category you've worked in".to_string());
+    }
+
+    if let Some(rating) = candidate.owner_rating {
+        let trust = candidate.owner_reviews.min(TRUSTED_REVIEWS) as f64 / TRUSTED_REVIEWS as f64;
+        score += RATING_WEIGHT * (rating - 3.0) / 2.0 * trust;
+        if rating >= 4.0 && candidate.owner_reviews >= TRUSTED_REVIEWS {
+            reasons.push("Posted by a well reviewed client".to_string());
+        }
+    }
+
+    let age_days = (Utc::now() - order.created_at).num_seconds().max(0) as f64 / 86_400.0;
+    score += FRESHNESS_WEIGHT * 0.5f64.powf(age_days / FRESHNESS_HALF_LIFE_DAYS);
+
+    (score, reasons)
+}
+
+/// Lowercase words separated by single spaces and padded with one on each
+/// side, so phrases can be looked up with `contains` without matching parts
+/// of words.
+fn words(text: &str) -> String {
+    let words = text
+        .split(|c: char| !c.is_alphanumeric() && c != '+' && c != '#')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    format!(" {words} ")
+}
@@ -0,0 +1,384 @@
+use std::{str::FromStr, time::Duration};
+
+use chrono::{DateTime, Datelike, Months, NaiveDate, NaiveDateTime, TimeDelta, Utc, Weekday};
+
+use crate::db::{postgres::PostgresDb, Db, OrderRecurrence};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How long the previous contractor has to take on a new order of the series
+/// before it goes public.
+const INVITE_WINDOW: TimeDelta = TimeDelta::hours(48);
+
+const MAX_INTERVAL: u32 = 100;
+
+/// Bounds the search for the next occurrence, far more than any rule needs.
+const MAX_PERIODS: u32 = 10_000;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// The subset of RFC 5545 recurrence rules orders support: `FREQ` (daily,
+/// weekly or monthly), `INTERVAL`, `BYDAY` for weekly rules, and either
+/// `COUNT` or `UNTIL`, e.g. `FREQ=WEEKLY;BYDAY=MO,TH;COUNT=10`.
+///
+/// Rules are evaluated in UTC, and monthly ones on the 29th to 31st fall on
+/// the last day of shorter months instead of skipping them.
+pub struct Rule {
+    frequency: Frequency,
+    interval: u32,
+    weekdays: Vec<Weekday>,
+    count: Option<usize>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut weekdays = Vec::new();
+        let mut count = None;
+        let mut until = None;
+        for part in s.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid rule part {part}"))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(format!("Unsupported frequency {value}")),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = value
+                        .parse()
+                        .ok()
+                        .filter(|interval| (1..=MAX_INTERVAL).contains(interval))
+                        .ok_or(format!("INTERVAL must be between 1 and {MAX_INTERVAL}"))?
+                }
+                "BYDAY" => {
+                    weekdays = value
+                        .split(',')
+                        .map(parse_weekday)
+                        .collect::<Result<_, _>>()?
+                }
+                "COUNT" => {
+                    count = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|&count: &usize| count > 0)
+                            .ok_or("COUNT must be a positive number")?,
+                    )
+                }
+                "UNTIL" => until = Some(parse_until(value)?),
+                _ => return Err(format!("Unsupported rule part {key}")),
+            }
+        }
+
+        let frequency = frequency.ok_or("FREQ is required")?;
+        if !weekdays.is_empty() && frequency != Frequency::Weekly {
+            return Err("BYDAY is only supported with FREQ=WEEKLY".to_string());
+        }
+        if count.is_some() && until.is_some() {
+            return Err("COUNT and UNTIL can't be combined".to_string());
+        }
+        weekdays.sort_by_key(Weekday::num_days_from_monday);
+        weekdays.dedup();
+        Ok(Rule {
+            frequency,
+            interval,
+            weekdays,
+            count,
+            until,
+        })
+    }
+}
+
+fn parse_weekday(day: &str) -> Result<Weekday, String> {
+    match day.to_ascii_uppercase().as_str() {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(format!("Invalid weekday {day}")),
+    }
+}
+
+fn parse_until(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(until) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Ok(until.and_utc());
+    }
+    // A bare date includes the whole day
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .map(|until| until.and_utc())
+        .ok_or_else(|| format!("Invalid UNTIL {value}"))
+}
+
+impl Rule {
+    /// The first occurrence later than `after` of a series starting at
+    /// `start` which already had `occurrences` of them, the start included.
+    /// `None` once the rule is exhausted.
+    pub fn next_after(
+        &self,
+        start: DateTime<Utc>,
+        after: DateTime<Utc>,
+        occurrences: usize,
+    ) -> Option<DateTime<Utc>> {
+        if self.count.is_some_and(|count| occurrences >= count) {
+            return None;
+        }
+        let next = (0..MAX_PERIODS)
+            .flat_map(|period| self.period(start, period))
+            .find(|&at| at > after)?;
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+
+    /// Occurrences within the `period`th repetition of the rule, in order.
+    fn period(&self, start: DateTime<Utc>, period: u32) -> Vec<DateTime<Utc>> {
+        let step = period * self.interval;
+        match self.frequency {
+            Frequency::Daily => vec![start + TimeDelta::days(step.into())],
+            Frequency::Monthly => start
+                .checked_add_months(Months::new(step))
+                .into_iter()
+                .collect(),
+            Frequency::Weekly if self.weekdays.is_empty() => {
+                vec![start + TimeDelta::weeks(step.into())]
+            }
+            Frequency::Weekly => {
+                let monday = start - TimeDelta::days(start.weekday().num_days_from_monday().into())
+                    + TimeDelta::weeks(step.into());
+                self.weekdays
+                    .iter()
+                    .map(|day| monday + TimeDelta::days(day.num_days_from_monday().into()))
+                    .filter(|&at| at >= start)
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Posts the next order of every series that is due.
+pub async fn run(db: PostgresDb) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        let recurrences = match db.get_due_recurrences().await {
+            Ok(recurrences) => recurrences,
+            Err(e) => {
+                eprintln!("Could not load recurring orders: {e}");
+                continue;
+            }
+        };
+        for recurrence in recurrences {
+            if let Err(e) = create_next(&db, &recurrence).await {
+                eprintln!(
+                    "Could not continue recurring order {}: {e}",
+                    recurrence.order_id
+                );
+            }
+        }
+    }
+}
+
+async fn create_next(db: &PostgresDb, recurrence: &OrderRecurrence) -> Result<(), String> {
+    let rule = recurrence.rule.parse::<Rule>()?;
+    let now = Utc::now();
+    // Occurrences missed while the server was down are skipped rather than
+    // posted all at once
+    let following = rule.next_after(
+        recurrence.starts_at,
+        recurrence.next_at.max(now),
+        recurrence.occurrences + 1,
+    );
+    let contractor = if recurrence.auto_offer {
+        db.get_accepted_offer_by_order_id(recurrence.order_id)
+            .await?
+            .map(|offer| offer.user_id)
+    } else {
+        None
+    };
+    let invite = contractor.map(|user_id| (user_id, now + INVITE_WINDOW));
+    let order = db.create_occurrence(recurrence, following, invite).await?;
+
+    match contractor {
+        Some(user_id) => {
+            let message = format!(
+                "\"{}\" is up again and reserved for you for {} hours",
+                order.order_name,
+                INVITE_WINDOW.num_hours()
+            );
+            db.notify_user(user_id, "recurring_invite", Some(order.order_id), &message)
+                .await
+        }
+        None => db.notify_saved_searches(order.order_id).await.map(|_| ()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, 0, 0).unwrap()
+    }
+
+    fn rule(rule: &str) -> Rule {
+        rule.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rule = rule("RRULE:freq=weekly;interval=2;byday=th,MO,TH;count=5");
+        assert!(rule.frequency == Frequency::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.weekdays, [Weekday::Mon, Weekday::Thu]);
+        assert_eq!(rule.count, Some(5));
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        for invalid in [
+            "",
+            "INTERVAL=2",
+            "FREQ=YEARLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;INTERVAL=101",
+            "FREQ=DAILY;COUNT=0",
+            "FREQ=DAILY;BYDAY=MO",
+            "FREQ=WEEKLY;BYDAY=XX",
+            "FREQ=DAILY;COUNT=3;UNTIL=20240101",
+            "FREQ=DAILY;UNTIL=2024-01-01",
+            "FREQ=DAILY;BYMONTH=1",
+            "FREQ",
+        ] {
+            assert!(invalid.parse::<Rule>().is_err(), "{invalid} was accepted");
+        }
+    }
+
+    #[test]
+    fn daily_with_interval() {
+        let rule = rule("FREQ=DAILY;INTERVAL=3");
+        let start = at(2024, 1, 1, 10);
+        assert_eq!(rule.next_after(start, start, 1), Some(at(2024, 1, 4, 10)));
+        assert_eq!(
+            rule.next_after(start, at(2024, 1, 4, 10), 2),
+            Some(at(2024, 1, 7, 10))
+        );
+    }
+
+    #[test]
+    fn skips_occurrences_in_the_past() {
+        let rule = rule("FREQ=DAILY");
+        let start = at(2024, 1, 1, 10);
+        assert_eq!(
+            rule.next_after(start, at(2024, 3, 1, 12), 1),
+            Some(at(2024, 3, 2, 10))
+        );
+    }
+
+    #[test]
+    fn count_includes_the_start() {
+        let rule = rule("FREQ=DAILY;COUNT=3");
+        let start = at(2024, 1, 1, 10);
+        let second = rule.next_after(start, start, 1).unwrap();
+        let third = rule.next_after(start, second, 2).unwrap();
+        assert_eq!(third, at(2024, 1, 3, 10));
+        assert_eq!(rule.next_after(start, third, 3), None);
+    }
+
+    #[test]
+    fn weekly_without_days_repeats_the_start() {
+        let rule = rule("FREQ=WEEKLY;INTERVAL=2");
+        let start = at(2024, 1, 3, 10);
+        assert_eq!(rule.next_after(start, start, 1), Some(at(2024, 1, 17, 10)));
+    }
+
+    #[test]
+    fn weekly_by_day() {
+        // Starts on a Wednesday, which isn't one of the days
+        let rule = rule("FREQ=WEEKLY;BYDAY=MO,TH");
+        let start = at(2024, 1, 3, 10);
+        let mut after = start;
+        let mut occurrences = Vec::new();
+        for n in 1..=4 {
+            after = rule.next_after(start, after, n).unwrap();
+            occurrences.push(after);
+        }
+        assert_eq!(
+            occurrences,
+            [
+                at(2024, 1, 4, 10),
+                at(2024, 1, 8, 10),
+                at(2024, 1, 11, 10),
+                at(2024, 1, 15, 10),
+            ]
+        );
+    }
+
+    #[test]
+    fn weekly_by_day_with_interval_skips_weeks() {
+        let rule = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH");
+        let start = at(2024, 1, 3, 10);
+        assert_eq!(
+            rule.next_after(start, at(2024, 1, 4, 10), 2),
+            Some(at(2024, 1, 15, 10))
+        );
+    }
+
+    #[test]
+    fn monthly_clamps_to_the_end_of_shorter_months() {
+        let rule = rule("FREQ=MONTHLY");
+        let start = at(2024, 1, 31, 10);
+        assert_eq!(rule.next_after(start, start, 1), Some(at(2024, 2, 29, 10)));
+        // Later months go back to the 31st rather than staying on the 29th
+        assert_eq!(
+            rule.next_after(start, at(2024, 2, 29, 10), 2),
+            Some(at(2024, 3, 31, 10))
+        );
+        assert_eq!(
+            rule.next_after(start, at(2024, 3, 31, 10), 3),
+            Some(at(2024, 4, 30, 10))
+        );
+    }
+
+    #[test]
+    fn until_as_a_date_includes_that_day() {
+        let rule = rule("FREQ=DAILY;UNTIL=20240103");
+        let start = at(2024, 1, 1, 10);
+        assert_eq!(
+            rule.next_after(start, at(2024, 1, 2, 10), 2),
+            Some(at(2024, 1, 3, 10))
+        );
+        assert_eq!(rule.next_after(start, at(2024, 1, 3, 10), 3), None);
+    }
+
+    #[test]
+    fn until_as_a_time_is_exact() {
+        let rule = rule("FREQ=DAILY;UNTIL=20240103T095959Z");
+        let start = at(2024, 1, 1, 10);
+        assert_eq!(rule.next_after(start, at(2024, 1, 2, 10), 2), None);
+    }
+}
@@ -0,0 +1,374 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::{sign_link, verify_link, Claims},
+    db::{postgres::PostgresDb, AttachmentInput, Db, Order},
+    routes::{delete_private_files, read_checked_files, store_checked_files},
+    storage::ImageStore,
+    AppState,
+};
+
+pub const MAX_FILE_SIZE: usize = 25 * 1024 * 1024;
+pub const MAX_FILES: usize = 5;
+/// Total size of private files a single user can keep around.
+pub const USER_QUOTA: usize = 200 * 1024 * 1024;
+const LINK_TTL_SECS: u64 = 15 * 60;
+const ALLOWED_TYPES: [&str; 16] = [
+    "application/pdf",
+    "application/zip",
+    "application/gzip",
+    "application/x-tar",
+    "application/x-7z-compressed",
+    "application/msword",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "application/vnd.oasis.opendocument.text",
+    "application/vnd.oasis.opendocument.spreadsheet",
+    "image/jpeg",
+    "image/png",
+    "image/webp",
+    "image/gif",
+    "text/plain",
+];
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route(
+            "/orders/{id}/attachments",
+            // Leave some room for the multipart boundaries and headers
+            post(upload_attachments_handler)
+                .layer(DefaultBodyLimit::max(MAX_FILES * MAX_FILE_SIZE + 64 * 1024)),
+        )
+        .route("/orders/{id}/attachments", get(get_attachments_handler))
+        .route("/attachments/{id}", delete(delete_attachment_handler))
+        .route("/attachments/{id}/link", get(get_link_handler))
+        .route("/attachments/{id}/download", get(download_handler))
+}
+
+/// The owner and whoever works on the order can always see its attachments.
+/// While the order is open and not hidden by a moderator any signed in user
+/// can, so they can quote a price.
+async fn can_access<D: Db>(db: &D, order: &Order, user_id: usize) -> Result<bool, String> {
+    if order.user_id == user_id || (order.status == "open" && order.hidden_at.is_none()) {
+        return Ok(true);
+    }
+    if db
+        .get_accepted_offer_by_order_id(order.order_id)
+        .await?
+        .is_some_and(|offer| offer.user_id == user_id)
+    {
+        return Ok(true);
+    }
+    Ok(db
+        .get_user_by_id(user_id)
+        .await?
+        .is_some_and(|user| user.is_moderator()))
+}
+
+/// Sniffs the content type from the bytes. Files no known signature matches
+/// are accepted as plain text if they are valid UTF-8, which covers source code.
+pub fn detect_type(data: &[u8]) -> Option<&'static str> {
+    match infer::get(data) {
+        Some(kind) => ALLOWED_TYPES
+            .iter()
+            .find(|&&allowed| allowed == kind.mime_type())
+            .copied(),
+        None if std::str::from_utf8(data).is_ok() => Some("text/plain"),
+        None => None,
+    }
+}
+
+/// Strips any directories and control characters from a client supplied name.
+pub fn sanitize_file_name(name: &str) -> String {
+    let name = name
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(200)
+        .collect::<String>();
+    match name.trim() {
+        "" | "." | ".." => "file".to_string(),
+        name => name.to_string(),
+    }
+}
+
+async fn upload_attachments_handler<D: Db>(
+    claims: Claims,
+    State(AppState {
+        db,
+        private_store,
+        scanner,
+        ..
+    }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.user_id == claims.sub => {}
+        Ok(Some(_)) => return StatusCode::FORBIDDEN.into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    // Check every file before storing any, so a bad one doesn't leave the
+    // others behind
+    let files = match read_checked_files(&scanner, multipart).await {
+        Ok((_, files)) => files,
+        Err(response) => return response,
+    };
+    let file_names = files
+        .iter()
+        .map(|file| file.file_name.clone())
+        .collect::<Vec<_>>();
+    let stored = match store_checked_files(&private_store, files).await {
+        Ok(stored) => stored,
+        Err(response) => return response,
+    };
+    let keys = stored
+        .iter()
+        .map(|file| file.storage_key.clone())
+        .collect::<Vec<_>>();
+    let attachments = file_names
+        .into_iter()
+        .zip(stored)
+        .map(|(file_name, file)| AttachmentInput {
+            order_id: id,
+            user_id: claims.sub,
+            file_name,
+            storage_key: file.storage_key,
+            content_type: file.content_type,
+            size: file.size,
+        })
+        .collect();
+
+    match db.create_attachments(attachments, USER_QUOTA).await {
+        Ok(Some(attachments)) => (StatusCode::CREATED, Json(attachments)).into_response(),
+        Ok(None) => {
+            delete_private_files(&private_store, &keys).await;
+            (StatusCode::PAYLOAD_TOO_LARGE, "Storage quota exceeded").into_response()
+        }
+        Err(e) => {
+            delete_private_files(&private_store, &keys).await;
+            (StatusCode::INTERNAL_SERVER_ERROR, e).into_response()
+        }
+    }
+}
+
+async fn get_attachments_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match can_access(&db, &order, claims.sub).await {
+        Ok(true) => {}
+        Ok(false) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.get_attachments_by_order_id(id).await {
+        Ok(attachments) => (StatusCode::OK, Json(attachments)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Serialize)]
+pub struct Link {
+    url: String,
+    expires: u64,
+}
+
+/// Signs `path` for the next few minutes.
+pub fn signed_link(path: String) -> Link {
+    let expires = chrono::Utc::now().timestamp() as u64 + LINK_TTL_SECS;
+    let signature = sign_link(&path, expires);
+    Link {
+        url: format!("{}?expires={}&signature={}", path, expires, signature),
+        expires,
+    }
+}
+
+/// Hands out a short-lived signed URL, so the file can be opened from a plain
+/// link without putting the user's token into it.
+async fn get_link_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let attachment = match db.get_attachment_by_id(id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(attachment.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match can_access(&db, &order, claims.sub).await {
+        Ok(true) => {}
+        Ok(false) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+
+    let path = format!("/attachments/{}/download", id);
+    (StatusCode::OK, Json(signed_link(path))).into_response()
+}
+
+#[derive(Deserialize)]
+pub struct DownloadQuery {
+    expires: u64,
+    signature: String,
+}
+
+impl DownloadQuery {
+    pub fn verify(&self, path: &str) -> bool {
+        verify_link(path, self.expires, &self.signature)
+    }
+}
+
+async fn download_handler<D: Db>(
+    State(AppState {
+        db, private_store, ..
+    }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Query(query): Query<DownloadQuery>,
+) -> impl IntoResponse {
+    if !query.verify(&format!("/attachments/{}/download", id)) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let attachment = match db.get_attachment_by_id(id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match private_store.get(&attachment.storage_key).await {
+        Ok(data) => serve_file(attachment.content_type, &attachment.file_name, data),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Sends a private file as a download.
+pub fn serve_file(content_type: String, file_name: &str, data: Vec<u8>) -> Response {
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::CONTENT_DISPOSITION, content_disposition(file_name)),
+            // Never let a browser render an uploaded file as something else
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff".to_string()),
+        ],
+        data,
+    )
+        .into_response()
+}
+
+/// Builds an `attachment` disposition with an ASCII fallback and the full
+/// UTF-8 name (RFC 6266).
+fn content_disposition(file_name: &str) -> String {
+    let fallback = file_name
+        .chars()
+        .map(|c| if c.is_ascii() { c } else { '_' })
+        .collect::<String>();
+    let encoded = file_name
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect::<String>();
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback, encoded
+    )
+}
+
+async fn delete_attachment_handler<D: Db>(
+    claims: Claims,
+    State(AppState {
+        db, private_store, ..
+    }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let attachment = match db.get_attachment_by_id(id).await {
+        Ok(Some(attachment)) => attachment,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if attachment.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if let Err(e) = db.delete_attachment(id).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    if let Err(e) = private_store.delete(&attachment.storage_key).await {
+        eprintln!(
+            "Could not delete stored file {}: {}",
+            attachment.storage_key, e
+        );
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_base_name() {
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(
+            sanitize_file_name("C:\\Users\\me\\essay.docx"),
+            "essay.docx"
+        );
+        assert_eq!(sanitize_file_name("report.pdf"), "report.pdf");
+    }
+
+    #[test]
+    fn strips_characters_that_break_the_header() {
+        assert_eq!(sanitize_file_name("a\"b\r\nc.txt"), "abc.txt");
+    }
+
+    #[test]
+    fn names_files_without_a_usable_name() {
+        for name in ["", "  ", ".", "..", "dir/", "\u{0}"] {
+            assert_eq!(sanitize_file_name(name), "file", "{name:?}");
+        }
+    }
+
+    #[test]
+    fn caps_the_name_length() {
+        assert_eq!(sanitize_file_name(&"ą".repeat(300)).chars().count(), 200);
+    }
+
+    #[test]
+    fn detects_allowed_types_by_content() {
+        assert_eq!(detect_type(b"%PDF-1.7\n"), Some("application/pdf"));
+        assert_eq!(
+            detect_type(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]),
+            Some("image/png")
+        );
+        assert_eq!(detect_type("fn main() {}\n".as_bytes()), Some("text/plain"));
+    }
+
+    #[test]
+    fn rejects_unknown_binaries_and_disallowed_types() {
+        assert_eq!(detect_type(&[0xFF, 0xFE, 0x00, 0x81]), None);
+        // Windows executable
+        assert_eq!(detect_type(b"MZ\x90\x00\x03\x00\x00\x00"), None);
+    }
+}
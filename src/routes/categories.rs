@@ -0,0 +1,189 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Category, CategoryInput, Db},
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/categories", get(get_categories_handler))
+        .route("/categories", post(create_category_handler))
+        .route("/categories/{id}", post(update_category_handler))
+        .route("/categories/{id}", delete(delete_category_handler))
+}
+
+async fn require_admin<D: Db>(db: &D, user_id: usize) -> Result<(), Response> {
+    match db.get_user_by_id(user_id).await {
+        Ok(Some(user)) if user.is_admin() => Ok(()),
+        Ok(_) => Err(StatusCode::FORBIDDEN.into_response()),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response()),
+    }
+}
+
+#[derive(Serialize)]
+struct CategoryNode {
+    #[serde(flatten)]
+    category: Category,
+    children: Vec<CategoryNode>,
+}
+
+fn build_tree(categories: &[Category], parent_id: Option<usize>) -> Vec<CategoryNode> {
+    categories
+        .iter()
+        .filter(|category| category.parent_id == parent_id)
+        .map(|category| CategoryNode {
+            category: category.clone(),
+            children: build_tree(categories, Some(category.category_id)),
+        })
+        .collect()
+}
+
+async fn get_categories_handler<D: Db>(
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_categories().await {
+        Ok(categories) => (StatusCode::OK, Json(build_tree(&categories, None))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct CategoryBody {
+    parent_id: Option<usize>,
+    name: String,
+    slug: String,
+}
+
+impl CategoryBody {
+    fn validate(&self) -> Result<(), &'static str> {
+        if self.name.trim().is_empty() || self.name.chars().count() > 50 {
+            return Err("Name must be between 1 and 50 characters");
+        }
+        if self.slug.is_empty()
+            || self.slug.len() > 50
+            || !self
+                .slug
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        {
+            return Err("Slug may only contain lowercase letters, digits and dashes");
+        }
+        Ok(())
+    }
+
+    fn into_input(self) -> CategoryInput {
+        CategoryInput {
+            parent_id: self.parent_id,
+            name: self.name.trim().to_string(),
+            slug: self.slug,
+        }
+    }
+}
+
+fn category_error(e: String) -> Response {
+    if e.contains("categories_slug_key") {
+        (StatusCode::CONFLICT, "Slug is already taken").into_response()
+    } else {
+        (StatusCode::INTERNAL_SERVER_ERROR, e).into_response()
+    }
+}
+
+async fn create_category_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Json(body): Json<CategoryBody>,
+) -> impl IntoResponse {
+    if let Err(response) = require_admin(&db, claims.sub).await {
+        return response;
+    }
+    if let Err(e) = body.validate() {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    if let Some(parent_id) = body.parent_id {
+        match db.get_category_by_id(parent_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return (StatusCode::BAD_REQUEST, "Unknown parent").into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        }
+    }
+    match db.create_category(body.into_input()).await {
+        Ok(category) => (StatusCode::CREATED, Json(category)).into_response(),
+        Err(e) => category_error(e),
+    }
+}
+
+async fn update_category_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(body): Json<CategoryBody>,
+) -> impl IntoResponse {
+    if let Err(response) = require_admin(&db, claims.sub).await {
+        return response;
+    }
+    if let Err(e) = body.validate() {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    let subtree = match db.get_category_subtree(id).await {
+        Ok(subtree) => subtree,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match db.get_category_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    if let Some(parent_id) = body.parent_id {
+        // Moving a category below itself would cut it off from the tree
+        if subtree.contains(&parent_id) {
+            return (
+                StatusCode::BAD_REQUEST,
+                "A category can't be moved into its own subtree",
+            )
+                .into_response();
+        }
+        match db.get_category_by_id(parent_id).await {
+            Ok(Some(_)) => {}
+            Ok(None) => return (StatusCode::BAD_REQUEST, "Unknown parent").into_response(),
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        }
+    }
+    match db.update_category(id, body.into_input()).await {
+        Ok(category) => (StatusCode::OK, Json(category)).into_response(),
+        Err(e) => category_error(e),
+    }
+}
+
+async fn delete_category_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    if let Err(response) = require_admin(&db, claims.sub).await {
+        return response;
+    }
+    match db.get_category_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    // Orders in the category simply lose it
+    match db.delete_category(id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (
+            StatusCode::CONFLICT,
+            "Move or delete the subcategories first",
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
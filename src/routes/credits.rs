@@ -0,0 +1,34 @@
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use serde_json::json;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db},
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/credits", get(get_balance_handler))
+        .route("/credits/transactions", get(get_transactions_handler))
+}
+
+async fn get_balance_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_balance(claims.sub).await {
+        Ok(balance) => (StatusCode::OK, Json(json!({ "balance": balance }))).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn get_transactions_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_transactions(claims.sub).await {
+        Ok(transactions) => (StatusCode::OK, Json(transactions)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
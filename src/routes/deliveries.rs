@@ -0,0 +1,273 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db, DeliveryInput, DeliveryOutcome, RevisionOutcome},
+    routes::{
+        attachments::{
+            serve_file, signed_link, DownloadQuery, MAX_FILES, MAX_FILE_SIZE, USER_QUOTA,
+        },
+        delete_private_files, read_checked_files, store_checked_files,
+    },
+    storage::ImageStore,
+    AppState,
+};
+
+/// How many times an owner can send a delivery back before they have to accept it.
+const MAX_REVISIONS: usize = 3;
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route(
+            "/orders/{id}/deliveries",
+            // Leave some room for the multipart boundaries and the note
+            post(create_delivery_handler)
+                .layer(DefaultBodyLimit::max(MAX_FILES * MAX_FILE_SIZE + 64 * 1024)),
+        )
+        .route("/orders/{id}/deliveries", get(get_deliveries_handler))
+        .route("/deliveries/{id}/accept", post(accept_delivery_handler))
+        .route(
+            "/deliveries/{id}/request-revision",
+            post(request_revision_handler),
+        )
+        .route(
+            "/deliveries/{id}/files/{file_id}/link",
+            get(get_link_handler),
+        )
+        .route(
+            "/deliveries/{id}/files/{file_id}/download",
+            get(download_handler),
+        )
+}
+
+async fn create_delivery_handler<D: Db>(
+    claims: Claims,
+    State(AppState {
+        db,
+        private_store,
+        scanner,
+        ..
+    }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let offer = match db.get_accepted_offer_by_order_id(id).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if offer.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if order.status != "assigned" {
+        return (StatusCode::CONFLICT, "Order is not in progress").into_response();
+    }
+
+    let (mut fields, files) = match read_checked_files(&scanner, multipart).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+    let Some(note) = fields.remove("note") else {
+        return (StatusCode::BAD_REQUEST, "A note is required").into_response();
+    };
+    let files = match store_checked_files(&private_store, files).await {
+        Ok(files) => files,
+        Err(response) => return response,
+    };
+    let keys = files
+        .iter()
+        .map(|file| file.storage_key.clone())
+        .collect::<Vec<_>>();
+
+    match db
+        .create_delivery(
+            DeliveryInput {
+                order_id: id,
+                user_id: claims.sub,
+                note,
+                files,
+            },
+            USER_QUOTA,
+        )
+        .await
+    {
+        Ok(DeliveryOutcome::Created(delivery)) => {
+            (StatusCode::CREATED, Json(delivery)).into_response()
+        }
+        Ok(DeliveryOutcome::NotAllowed) => {
+            delete_private_files(&private_store, &keys).await;
+            (
+                StatusCode::CONFLICT,
+                "A delivery is already awaiting review",
+            )
+                .into_response()
+        }
+        Ok(DeliveryOutcome::OverQuota) => {
+            delete_private_files(&private_store, &keys).await;
+            (StatusCode::PAYLOAD_TOO_LARGE, "Storage quota exceeded").into_response()
+        }
+        Err(e) => {
+            delete_private_files(&private_store, &keys).await;
+            (StatusCode::INTERNAL_SERVER_ERROR, e).into_response()
+        }
+    }
+}
+
+async fn get_deliveries_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let contractor = match db.get_accepted_offer_by_order_id(id).await {
+        Ok(offer) => offer.map(|offer| offer.user_id),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub && contractor != Some(claims.sub) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    match db.get_deliveries_by_order_id(id).await {
+        Ok(deliveries) => (StatusCode::OK, Json(deliveries)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn accept_delivery_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let delivery = match db.get_delivery_by_id(id).await {
+        Ok(Some(delivery)) => delivery,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(delivery.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if delivery.status != "pending" || order.status != "assigned" {
+        return (StatusCode::CONFLICT, "Delivery is not awaiting review").into_response();
+    }
+    match db.accept_delivery(id).await {
+        Ok(Some(delivery)) => (StatusCode::OK, Json(delivery)).into_response(),
+        Ok(None) => (StatusCode::CONFLICT, "Delivery is not awaiting review").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct RevisionBody {
+    feedback: String,
+}
+
+async fn request_revision_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(RevisionBody { feedback }): Json<RevisionBody>,
+) -> impl IntoResponse {
+    let delivery = match db.get_delivery_by_id(id).await {
+        Ok(Some(delivery)) => delivery,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(delivery.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if delivery.status != "pending" || order.status != "assigned" {
+        return (StatusCode::CONFLICT, "Delivery is not awaiting review").into_response();
+    }
+    match db.request_revision(id, &feedback, MAX_REVISIONS).await {
+        Ok(RevisionOutcome::Requested(delivery)) => {
+            (StatusCode::OK, Json(delivery)).into_response()
+        }
+        Ok(RevisionOutcome::NotPending) => {
+            (StatusCode::CONFLICT, "Delivery is not awaiting review").into_response()
+        }
+        Ok(RevisionOutcome::LimitReached) => {
+            (StatusCode::CONFLICT, "Revision limit reached").into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Hands out a signed URL for a delivered file, to the parties of the order.
+async fn get_link_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path((id, file_id)): Path<(usize, usize)>,
+) -> impl IntoResponse {
+    let delivery = match db.get_delivery_by_id(id).await {
+        Ok(Some(delivery)) => delivery,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(delivery.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub && delivery.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if !delivery.files.iter().any(|file| file.file_id == file_id) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let path = format!("/deliveries/{}/files/{}/download", id, file_id);
+    (StatusCode::OK, Json(signed_link(path))).into_response()
+}
+
+async fn download_handler<D: Db>(
+    State(AppState {
+        db, private_store, ..
+    }): State<AppState<D>>,
+    Path((id, file_id)): Path<(usize, usize)>,
+    Query(query): Query<DownloadQuery>,
+) -> impl IntoResponse {
+    if !query.verify(&format!("/deliveries/{}/files/{}/download", id, file_id)) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let file = match db.get_delivery_by_id(id).await {
+        Ok(Some(delivery)) => match delivery
+            .files
+            .into_iter()
+            .find(|file| file.file_id == file_id)
+        {
+            Some(file) => file,
+            None => return StatusCode::NOT_FOUND.into_response(),
+        },
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match private_store.get(&file.storage_key).await {
+        Ok(data) => serve_file(file.content_type, &file.storage_key, data),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
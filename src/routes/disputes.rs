@@ -0,0 +1,426 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{
+        postgres::PostgresDb, Db, DisputeInput, DisputeOutcome, DisputeResolution, Order,
+        ResolveOutcome,
+    },
+    money::validate_amount,
+    routes::{
+        attachments::{
+            serve_file, signed_link, DownloadQuery, MAX_FILES, MAX_FILE_SIZE, USER_QUOTA,
+        },
+        delete_private_files, read_checked_files, store_checked_files,
+    },
+    storage::ImageStore,
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route(
+            "/orders/{id}/disputes",
+            // Leave some room for the multipart boundaries and the reason
+            post(open_dispute_handler)
+                .layer(DefaultBodyLimit::max(MAX_FILES * MAX_FILE_SIZE + 64 * 1024)),
+        )
+        .route("/disputes", get(get_open_disputes_handler))
+        .route("/disputes/{id}", get(get_dispute_handler))
+        .route(
+            "/disputes/{id}/evidence",
+            // Leave some room for the multipart boundaries and headers
+            post(add_evidence_handler)
+                .layer(DefaultBodyLimit::max(MAX_FILES * MAX_FILE_SIZE + 64 * 1024)),
+        )
+        .route("/disputes/{id}/messages", get(get_dispute_messages_handler))
+        .route(
+            "/disputes/{id}/messages",
+            post(send_dispute_message_handler),
+        )
+        .route("/disputes/{id}/resolve", post(resolve_dispute_handler))
+        .route(
+            "/disputes/{id}/evidence/{file_id}/link",
+            get(get_evidence_link_handler),
+        )
+        .route(
+            "/disputes/{id}/evidence/{file_id}/download",
+            get(download_evidence_handler),
+        )
+}
+
+/// Returns the id of the contractor whose offer was accepted for `order`.
+async fn contractor_of<D: Db>(db: &D, order: &Order) -> Result<Option<usize>, String> {
+    Ok(db
+        .get_accepted_offer_by_order_id(order.order_id)
+        .await?
+        .map(|offer| offer.user_id))
+}
+
+/// Both parties of the order and moderators can see a dispute.
+async fn can_view<D: Db>(db: &D, order: &Order, user_id: usize) -> Result<bool, String> {
+    if order.user_id == user_id || contractor_of(db, order).await? == Some(user_id) {
+        return Ok(true);
+    }
+    Ok(db
+        .get_user_by_id(user_id)
+        .await?
+        .is_some_and(|user| user.is_moderator()))
+}
+
+async fn open_dispute_handler<D: Db>(
+    claims: Claims,
+    State(AppState {
+        db,
+        private_store,
+        scanner,
+        ..
+    }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let contractor = match contractor_of(&db, &order).await {
+        Ok(contractor) => contractor,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub && contractor != Some(claims.sub) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if order.status != "assigned" {
+        return (
+            StatusCode::CONFLICT,
+            "Only orders in progress can be disputed",
+        )
+            .into_response();
+    }
+    let (mut fields, evidence) = match read_checked_files(&scanner, multipart).await {
+        Ok(request) => request,
+        Err(response) => return response,
+    };
+    let Some(reason) = fields.remove("reason") else {
+        return (StatusCode::BAD_REQUEST, "A reason is required").into_response();
+    };
+    let evidence = match store_checked_files(&private_store, evidence).await {
+        Ok(evidence) => evidence,
+        Err(response) => return response,
+    };
+    let keys = evidence
+        .iter()
+        .map(|file| file.storage_key.clone())
+        .collect::<Vec<_>>();
+
+    match db
+        .create_dispute(
+            DisputeInput {
+                order_id: id,
+                opened_by: claims.sub,
+                reason,
+                evidence,
+            },
+            USER_QUOTA,
+        )
+        .await
+    {
+        Ok(DisputeOutcome::Opened(dispute)) => (StatusCode::CREATED, Json(dispute)).into_response(),
+        Ok(DisputeOutcome::NotInProgress) => {
+            delete_private_files(&private_store, &keys).await;
+            (
+                StatusCode::CONFLICT,
+                "Only orders in progress can be disputed",
+            )
+                .into_response()
+        }
+        Ok(DisputeOutcome::OverQuota) => {
+            delete_private_files(&private_store, &keys).await;
+            (StatusCode::PAYLOAD_TOO_LARGE, "Storage quota exceeded").into_response()
+        }
+        Err(e) => {
+            delete_private_files(&private_store, &keys).await;
+            (StatusCode::INTERNAL_SERVER_ERROR, e).into_response()
+        }
+    }
+}
+
+async fn get_open_disputes_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_user_by_id(claims.sub).await {
+        Ok(Some(user)) if user.is_moderator() => {}
+        Ok(_) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.get_open_disputes().await {
+        Ok(disputes) => (StatusCode::OK, Json(disputes)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn get_dispute_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let dispute = match db.get_dispute_by_id(id).await {
+        Ok(Some(dispute)) => dispute,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(dispute.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match can_view(&db, &order, claims.sub).await {
+        Ok(true) => (StatusCode::OK, Json(dispute)).into_response(),
+        Ok(false) => StatusCode::FORBIDDEN.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn add_evidence_handler<D: Db>(
+    claims: Claims,
+    State(AppState {
+        db,
+        private_store,
+        scanner,
+        ..
+    }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    let dispute = match db.get_dispute_by_id(id).await {
+        Ok(Some(dispute)) => dispute,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(dispute.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match can_view(&db, &order, claims.sub).await {
+        Ok(true) => {}
+        Ok(false) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    if dispute.status != "open" {
+        return (StatusCode::CONFLICT, "Dispute is already resolved").into_response();
+    }
+    let evidence = match read_checked_files(&scanner, multipart).await {
+        Ok((_, evidence)) => evidence,
+        Err(response) => return response,
+    };
+    let evidence = match store_checked_files(&private_store, evidence).await {
+        Ok(evidence) => evidence,
+        Err(response) => return response,
+    };
+    let keys = evidence
+        .iter()
+        .map(|file| file.storage_key.clone())
+        .collect::<Vec<_>>();
+
+    match db
+        .add_dispute_evidence(id, claims.sub, &evidence, USER_QUOTA)
+        .await
+    {
+        Ok(Some(dispute)) => (StatusCode::OK, Json(dispute)).into_response(),
+        Ok(None) => {
+            delete_private_files(&private_store, &keys).await;
+            (StatusCode::PAYLOAD_TOO_LARGE, "Storage quota exceeded").into_response()
+        }
+        Err(e) => {
+            delete_private_files(&private_store, &keys).await;
+            (StatusCode::INTERNAL_SERVER_ERROR, e).into_response()
+        }
+    }
+}
+
+async fn get_dispute_messages_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let dispute = match db.get_dispute_by_id(id).await {
+        Ok(Some(dispute)) => dispute,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(dispute.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match can_view(&db, &order, claims.sub).await {
+        Ok(true) => {}
+        Ok(false) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.get_dispute_messages(id).await {
+        Ok(messages) => (StatusCode::OK, Json(messages)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct DisputeMessageBody {
+    content: String,
+}
+
+async fn send_dispute_message_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(DisputeMessageBody { content }): Json<DisputeMessageBody>,
+) -> impl IntoResponse {
+    let dispute = match db.get_dispute_by_id(id).await {
+        Ok(Some(dispute)) => dispute,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(dispute.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match can_view(&db, &order, claims.sub).await {
+        Ok(true) => {}
+        Ok(false) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.create_dispute_message(id, claims.sub, &content).await {
+        Ok(message) => (StatusCode::CREATED, Json(message)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ResolveBody {
+    resolution: String, // release, refund or split
+    contractor_amount: Option<Decimal>,
+    note: String,
+}
+
+async fn resolve_dispute_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(body): Json<ResolveBody>,
+) -> impl IntoResponse {
+    match db.get_user_by_id(claims.sub).await {
+        Ok(Some(user)) if user.is_moderator() => {}
+        Ok(_) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    let dispute = match db.get_dispute_by_id(id).await {
+        Ok(Some(dispute)) => dispute,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(dispute.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let valid = match (body.resolution.as_str(), body.contractor_amount) {
+        ("release" | "refund", None) => true,
+        ("split", Some(amount)) => validate_amount(amount).is_ok() && amount <= order.price,
+        _ => false,
+    };
+    if !valid {
+        return (StatusCode::BAD_REQUEST, "Invalid resolution").into_response();
+    }
+
+    match db
+        .resolve_dispute(
+            id,
+            DisputeResolution {
+                resolution: body.resolution,
+                contractor_amount: body.contractor_amount,
+                note: body.note,
+                resolved_by: claims.sub,
+            },
+        )
+        .await
+    {
+        Ok(ResolveOutcome::Resolved(dispute)) => (StatusCode::OK, Json(dispute)).into_response(),
+        Ok(ResolveOutcome::NotOpen) => {
+            (StatusCode::CONFLICT, "Dispute is already resolved").into_response()
+        }
+        Ok(ResolveOutcome::ExceedsEscrow) => {
+            (StatusCode::CONFLICT, "Split exceeds what is left in escrow").into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Hands out a signed URL for a piece of evidence, to whoever can see the
+/// dispute.
+async fn get_evidence_link_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path((id, file_id)): Path<(usize, usize)>,
+) -> impl IntoResponse {
+    let dispute = match db.get_dispute_by_id(id).await {
+        Ok(Some(dispute)) => dispute,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(dispute.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match can_view(&db, &order, claims.sub).await {
+        Ok(true) => {}
+        Ok(false) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    if !dispute.evidence.iter().any(|file| file.file_id == file_id) {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let path = format!("/disputes/{}/evidence/{}/download", id, file_id);
+    (StatusCode::OK, Json(signed_link(path))).into_response()
+}
+
+async fn download_evidence_handler<D: Db>(
+    State(AppState {
+        db, private_store, ..
+    }): State<AppState<D>>,
+    Path((id, file_id)): Path<(usize, usize)>,
+    Query(query): Query<DownloadQuery>,
+) -> impl IntoResponse {
+    if !query.verify(&format!("/disputes/{}/evidence/{}/download", id, file_id)) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let file = match db.get_dispute_by_id(id).await {
+        Ok(Some(dispute)) => match dispute
+            .evidence
+            .into_iter()
+            .find(|file| file.file_id == file_id)
+        {
+            Some(file) => file,
+            None => return StatusCode::NOT_FOUND.into_response(),
+        },
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match private_store.get(&file.storage_key).await {
+        Ok(data) => serve_file(file.content_type, &file.storage_key, data),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
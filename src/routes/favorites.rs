@@ -0,0 +1,66 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db},
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route(
+            "/orders/{id}/favorite",
+            post(add_favorite_handler).delete(remove_favorite_handler),
+        )
+        .route("/user/me/favorites", get(get_favorites_handler))
+}
+
+async fn add_favorite_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.user_id == claims.sub => {
+            return (StatusCode::BAD_REQUEST, "You can't favorite your own order").into_response()
+        }
+        Ok(Some(order)) if order.status == "draft" || order.hidden_at.is_some() => {
+            return StatusCode::NOT_FOUND.into_response()
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.add_favorite(claims.sub, id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn remove_favorite_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    match db.remove_favorite(claims.sub, id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn get_favorites_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_favorite_orders(claims.sub).await {
+        Ok(orders) => (StatusCode::OK, Json(orders)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
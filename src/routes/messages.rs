@@ -30,7 +30,7 @@ struct MessageBody {
 
 async fn send_message_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Json(msg): Json<MessageBody>,
 ) -> impl IntoResponse {
     match db
@@ -48,7 +48,7 @@ async fn send_message_handler<D: Db>(
 
 async fn delete_message_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     let msg = match db.get_message_by_id(id).await {
@@ -72,7 +72,7 @@ struct MessageUpdateBody {
 
 async fn update_message_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
     Json(MessageUpdateBody { content }): Json<MessageUpdateBody>,
 ) -> impl IntoResponse {
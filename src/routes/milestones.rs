@@ -0,0 +1,201 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db, MilestoneInput},
+    money::validate_amount,
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/offers/{id}/milestones", post(create_milestones_handler))
+        .route("/offers/{id}/milestones", get(get_milestones_handler))
+        .route("/milestones/{id}/submit", post(submit_milestone_handler))
+        .route("/milestones/{id}/approve", post(approve_milestone_handler))
+        .route(
+            "/milestones/{id}/request-changes",
+            post(request_changes_handler),
+        )
+}
+
+async fn create_milestones_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(milestones): Json<Vec<MilestoneInput>>,
+) -> impl IntoResponse {
+    let offer = match db.get_offer_by_id(id).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(offer.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if offer.status != "accepted" {
+        return (StatusCode::CONFLICT, "Offer has not been accepted").into_response();
+    }
+    if milestones.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Invalid milestone amounts").into_response();
+    }
+    if let Err(e) = milestones
+        .iter()
+        .try_for_each(|m| validate_amount(m.amount))
+    {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    if milestones.iter().map(|m| m.amount).sum::<Decimal>() != order.price {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Milestone amounts must add up to the order price",
+        )
+            .into_response();
+    }
+
+    match db.create_milestones(id, milestones).await {
+        Ok(Some(milestones)) => (StatusCode::CREATED, Json(milestones)).into_response(),
+        Ok(None) => (StatusCode::CONFLICT, "Offer already has milestones").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Only the order owner and the contractor can see the milestones.
+async fn get_milestones_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let offer = match db.get_offer_by_id(id).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(offer.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub && offer.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    match db.get_milestones_by_offer_id(id).await {
+        Ok(milestones) => (StatusCode::OK, Json(milestones)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn submit_milestone_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let milestone = match db.get_milestone_by_id(id).await {
+        Ok(Some(milestone)) => milestone,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let offer = match db.get_offer_by_id(milestone.offer_id).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if offer.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    match db
+        .update_milestone_status(id, &["pending", "changes_requested"], "submitted", None)
+        .await
+    {
+        Ok(Some(milestone)) => (StatusCode::OK, Json(milestone)).into_response(),
+        Ok(None) => (StatusCode::CONFLICT, "Milestone cannot be submitted").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn approve_milestone_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    review_milestone(db, claims, id, "approved", None).await
+}
+
+#[derive(Deserialize)]
+struct ChangesBody {
+    feedback: String,
+}
+
+async fn request_changes_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(ChangesBody { feedback }): Json<ChangesBody>,
+) -> impl IntoResponse {
+    review_milestone(db, claims, id, "changes_requested", Some(&feedback)).await
+}
+
+/// Moves a submitted milestone to `status` on behalf of the order owner.
+/// Approving pays the milestone out of escrow, whatever is left goes to the
+/// contractor when the delivery is accepted.
+async fn review_milestone<D: Db>(
+    db: D,
+    claims: Claims,
+    id: usize,
+    status: &str,
+    feedback: Option<&str>,
+) -> Response {
+    let milestone = match db.get_milestone_by_id(id).await {
+        Ok(Some(milestone)) => milestone,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let offer = match db.get_offer_by_id(milestone.offer_id).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match db.get_order_by_id(offer.order_id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if milestone.status != "submitted" {
+        return (StatusCode::CONFLICT, "Milestone has not been submitted").into_response();
+    }
+    if status != "approved" {
+        return match db
+            .update_milestone_status(id, &["submitted"], status, feedback)
+            .await
+        {
+            Ok(Some(milestone)) => (StatusCode::OK, Json(milestone)).into_response(),
+            Ok(None) => (StatusCode::CONFLICT, "Milestone has not been submitted").into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        };
+    }
+    match db.approve_milestone(id).await {
+        Ok(Some(milestone)) => (StatusCode::OK, Json(milestone)).into_response(),
+        Ok(None) => (
+            StatusCode::CONFLICT,
+            "Milestone has not been submitted or the order is no longer in progress",
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
@@ -1,12 +1,157 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::Multipart,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
 use serde::Deserialize;
 
+use crate::{
+    db::PrivateFileInput,
+    routes::attachments::{detect_type, sanitize_file_name, MAX_FILES, MAX_FILE_SIZE},
+    scanner::{ScanResult, Scanner},
+    storage::{ImageStore, Storage},
+};
+
+pub mod attachments;
+pub mod categories;
+pub mod credits;
+pub mod deliveries;
+pub mod disputes;
+pub mod favorites;
 pub mod messages;
+pub mod milestones;
+pub mod notifications;
 pub mod offers;
+pub mod order_images;
 pub mod orders;
+pub mod recommendations;
+pub mod recurrences;
+pub mod reports;
 pub mod reviews;
+pub mod revisions;
+pub mod saved_searches;
+pub mod stats;
+pub mod suspensions;
+pub mod tags;
+pub mod templates;
+pub mod uploads;
 pub mod user;
 
 #[derive(Deserialize)]
 struct SearchQuery {
     query: String,
 }
+
+/// A file of a multipart request that passed the type check and the virus
+/// scan, but isn't stored yet.
+struct CheckedFile {
+    file_name: String,
+    content_type: &'static str,
+    data: Vec<u8>,
+}
+
+/// Reads a multipart request into its text fields and files. Every file is
+/// held to the attachment limits, sniffed and scanned before the caller
+/// stores any of them.
+async fn read_checked_files(
+    scanner: &Scanner,
+    mut multipart: Multipart,
+) -> Result<(HashMap<String, String>, Vec<CheckedFile>), Response> {
+    let mut fields = HashMap::new();
+    let mut files = Vec::new();
+    loop {
+        let mut field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Err((e.status(), e.body_text()).into_response()),
+        };
+        let Some(file_name) = field.file_name().map(sanitize_file_name) else {
+            let name = field.name().unwrap_or_default().to_string();
+            let text = field
+                .text()
+                .await
+                .map_err(|e| (e.status(), e.body_text()).into_response())?;
+            fields.insert(name, text);
+            continue;
+        };
+        if files.len() == MAX_FILES {
+            return Err((StatusCode::PAYLOAD_TOO_LARGE, "Too many files").into_response());
+        }
+
+        let mut data = Vec::new();
+        loop {
+            match field.chunk().await {
+                Ok(Some(chunk)) if data.len() + chunk.len() > MAX_FILE_SIZE => {
+                    return Err((StatusCode::PAYLOAD_TOO_LARGE, "File is too large").into_response())
+                }
+                Ok(Some(chunk)) => data.extend_from_slice(&chunk),
+                Ok(None) => break,
+                Err(e) => return Err((e.status(), e.body_text()).into_response()),
+            }
+        }
+        // Trust the bytes, not the header the client sent
+        let Some(content_type) = detect_type(&data) else {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "This file type can't be attached",
+            )
+                .into_response());
+        };
+        match scanner.scan(&data).await {
+            Ok(ScanResult::Clean) => {}
+            Ok(ScanResult::Infected(signature)) => {
+                return Err((
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!("{file_name} was flagged as {signature}"),
+                )
+                    .into_response())
+            }
+            Err(e) => return Err((StatusCode::SERVICE_UNAVAILABLE, e).into_response()),
+        }
+        files.push(CheckedFile {
+            file_name,
+            content_type,
+            data,
+        });
+    }
+    Ok((fields, files))
+}
+
+/// Puts checked files into the private store, in order. Either all of them
+/// end up stored or none do.
+async fn store_checked_files(
+    private_store: &Storage,
+    files: Vec<CheckedFile>,
+) -> Result<Vec<PrivateFileInput>, Response> {
+    let mut stored = Vec::with_capacity(files.len());
+    for file in files {
+        let size = file.data.len();
+        match private_store.put(file.data, file.content_type).await {
+            Ok(object) => stored.push(PrivateFileInput {
+                storage_key: object.key,
+                content_type: file.content_type.to_string(),
+                size,
+            }),
+            Err(e) => {
+                let keys = stored
+                    .into_iter()
+                    .map(|file| file.storage_key)
+                    .collect::<Vec<_>>();
+                delete_private_files(private_store, &keys).await;
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response());
+            }
+        }
+    }
+    Ok(stored)
+}
+
+/// Removes files from the private store, logging the ones that stay behind.
+async fn delete_private_files(private_store: &Storage, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = private_store.delete(key).await {
+            eprintln!("Could not delete stored file {key}: {e}");
+        }
+    }
+}
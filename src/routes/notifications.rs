@@ -0,0 +1,56 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db},
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/notifications", get(get_notifications_handler))
+        .route("/notifications/read", post(mark_read_handler))
+}
+
+#[derive(Deserialize)]
+struct NotificationsQuery {
+    #[serde(default)]
+    unread: bool,
+}
+
+async fn get_notifications_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Query(query): Query<NotificationsQuery>,
+) -> impl IntoResponse {
+    match db.get_notifications(claims.sub, query.unread).await {
+        Ok(notifications) => (StatusCode::OK, Json(notifications)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct MarkReadBody {
+    notification_ids: Option<Vec<usize>>, // All of them if missing
+}
+
+async fn mark_read_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Json(body): Json<MarkReadBody>,
+) -> impl IntoResponse {
+    match db
+        .mark_notifications_read(claims.sub, body.notification_ids.as_deref())
+        .await
+    {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
@@ -6,15 +6,12 @@ use axum::{
     Json, Router,
 };
 
-use lettre::message::{Message, SinglePart};
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::{SmtpTransport, Transport};
-
 use serde::Deserialize;
 
 use crate::{
     auth::Claims,
-    db::{postgres::PostgresDb, Db, OfferInput, Order},
+    db::{postgres::PostgresDb, AcceptOutcome, Db, OfferInput},
+    mail::escape_html,
     AppState,
 };
 
@@ -30,64 +27,48 @@ pub fn router() -> Router<AppState<PostgresDb>> {
 
 async fn create_offer_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, mailer, .. }): State<AppState<D>>,
     Json(offer): Json<OfferInput>,
 ) -> impl IntoResponse {
+    let order = match db.get_order_by_id(offer.order_id).await {
+        Ok(Some(order)) if order.status != "draft" && order.hidden_at.is_none() => order,
+        Ok(_) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
     match db.create_offer(offer, claims.sub).await {
-        Ok(offer) => {
-            let who = db.get_user_by_id(offer.user_id).await.unwrap().unwrap();
-            dbg!(&who.username);
-            let what = db.get_order_by_id(offer.order_id).await.unwrap().unwrap();
-            let orderer = db.get_user_by_id(what.user_id).await.unwrap().unwrap();
-            send_email(&orderer.email, &who.username, &what.order_name).await;
+        Ok(Some(offer)) => {
+            // The offer stands even if the owner can't be told about it
+            match (
+                db.get_user_by_id(offer.user_id).await,
+                db.get_user_by_id(order.user_id).await,
+            ) {
+                (Ok(Some(bidder)), Ok(Some(owner))) => {
+                    mailer
+                        .send(
+                            &owner.email,
+                            "Ktos odpowiedzial na twoje ogloszenie",
+                            format!(
+                                "{} odpowiedzial na twoje zgloszenie: {}",
+                                escape_html(&bidder.username),
+                                escape_html(&order.order_name)
+                            ),
+                        )
+                        .await
+                }
+                _ => eprintln!(
+                    "Could not tell the owner of order {} about offer {}",
+                    order.order_id, offer.offer_id
+                ),
+            }
             (StatusCode::CREATED, Json(offer)).into_response()
         }
+        Ok(None) => (StatusCode::CONFLICT, "Order is not taking offers").into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
 
-async fn send_email(recipient: &str, who: &str, what: &str) {
-    // Build the email
-    let email = Message::builder()
-        .from(
-            "techni zamowienia <technizamowienia@gmail.com>"
-                .parse()
-                .unwrap(),
-        )
-        .to(recipient.parse().unwrap())
-        .subject("Ktos odpowiedzial na twoje ogloszenie")
-        .singlepart(
-            SinglePart::builder()
-                .header(lettre::message::header::ContentType::TEXT_HTML)
-                .body(format!(
-                    "{} odpowiedzial na twoje zgloszenie: {}",
-                    who, what
-                )),
-        )
-        .unwrap();
-
-    // SMTP credentials (use your Gmail address + App Password)
-    let creds = Credentials::new(
-        "technizamowienia".to_string(),
-        "znfbjezwxvremoln".to_string(),
-    );
-    dbg!(recipient);
-
-    // Open a remote connection to Gmail
-    let mailer = SmtpTransport::relay("smtp.gmail.com")
-        .unwrap()
-        .credentials(creds)
-        .build();
-
-    // Send the email
-    match mailer.send(&email) {
-        Ok(_) => println!("Email sent successfully!"),
-        Err(e) => eprintln!("Could not send email: {:?}", e),
-    }
-}
-
 async fn get_offers_by_user_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     match db.get_offers_by_user_id(id).await {
@@ -97,7 +78,7 @@ async fn get_offers_by_user_handler<D: Db>(
 }
 
 async fn get_offers_by_order_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     match db.get_offers_by_order_id(id).await {
@@ -113,7 +94,7 @@ struct OfferUpdateBody {
 
 async fn update_offer_status_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
     Json(OfferUpdateBody { status }): Json<OfferUpdateBody>,
 ) -> impl IntoResponse {
@@ -130,15 +111,53 @@ async fn update_offer_status_handler<D: Db>(
     if order.user_id != claims.sub {
         return StatusCode::FORBIDDEN.into_response();
     }
-    match db.update_offer_status(id, &status).await {
-        Ok(offer) => (StatusCode::OK, Json(offer)).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    match status.as_str() {
+        "accepted" => {}
+        "rejected" => {
+            return match db.reject_offer(id).await {
+                Ok(Some(offer)) => (StatusCode::OK, Json(offer)).into_response(),
+                Ok(None) => {
+                    (StatusCode::CONFLICT, "Only pending offers can be rejected").into_response()
+                }
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+            }
+        }
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Status must be accepted or rejected",
+            )
+                .into_response()
+        }
+    }
+
+    let offer = match db.accept_offer(id).await {
+        Ok(AcceptOutcome::Accepted(offer)) => offer,
+        Ok(AcceptOutcome::NotOpen) => {
+            return (
+                StatusCode::CONFLICT,
+                "Order already has a contractor or the offer was withdrawn",
+            )
+                .into_response()
+        }
+        Ok(AcceptOutcome::InsufficientCredits) => {
+            return (StatusCode::PAYMENT_REQUIRED, "Insufficient credits").into_response()
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let message = format!("\"{}\" has been assigned to a contractor", order.order_name);
+    if let Err(e) = db
+        .notify_favorites(order.order_id, "assigned", &message)
+        .await
+    {
+        eprintln!("Could not notify favorites: {e}");
     }
+    (StatusCode::OK, Json(offer)).into_response()
 }
 
 async fn delete_offer_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     let offer = match db.get_offer_by_id(id).await {
@@ -149,14 +168,15 @@ async fn delete_offer_handler<D: Db>(
     if offer.user_id != claims.sub {
         return StatusCode::FORBIDDEN.into_response();
     }
-    match db.delete_offer(id).await {
-        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+    match db.withdraw_offer(id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (StatusCode::CONFLICT, "Only pending offers can be withdrawn").into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
 
 async fn get_offer_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     match db.get_offer_by_id(id).await {
@@ -0,0 +1,133 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, post},
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db},
+    routes::uploads::{check_uploads, delete_stored},
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route(
+            "/orders/{id}/images",
+            post(add_images_handler).put(reorder_images_handler),
+        )
+        .route(
+            "/orders/{id}/images/{image_id}",
+            delete(remove_image_handler),
+        )
+}
+
+#[derive(Deserialize)]
+struct AddImagesBody {
+    upload_ids: Vec<usize>, // From POST /uploads
+}
+
+async fn add_images_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(body): Json<AddImagesBody>,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.user_id == claims.sub => {}
+        Ok(Some(_)) => return StatusCode::FORBIDDEN.into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    if let Err(e) = check_uploads(&db, claims.sub, &body.upload_ids).await {
+        return e.into_response();
+    }
+    if let Err(e) = db.add_order_images(id, &body.upload_ids).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) => (StatusCode::OK, Json(order)).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ReorderImagesBody {
+    image_ids: Vec<usize>,
+}
+
+async fn reorder_images_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(body): Json<ReorderImagesBody>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    // The new order has to mention every image of the order exactly once
+    let mut current = order
+        .images
+        .iter()
+        .map(|image| image.image_id)
+        .collect::<Vec<_>>();
+    let mut requested = body.image_ids.clone();
+    current.sort_unstable();
+    requested.sort_unstable();
+    if current != requested {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Every image of the order has to be listed exactly once",
+        )
+            .into_response();
+    }
+
+    if let Err(e) = db.reorder_order_images(id, &body.image_ids).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) => (StatusCode::OK, Json(order)).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn remove_image_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, store, .. }): State<AppState<D>>,
+    Path((id, image_id)): Path<(usize, usize)>,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.user_id == claims.sub => {}
+        Ok(Some(_)) => return StatusCode::FORBIDDEN.into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    let upload_id = match db.remove_order_image(id, image_id).await {
+        Ok(Some(upload_id)) => upload_id,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    // The same upload can be attached to other orders, those keep the file
+    match db.delete_unused_uploads(&[upload_id]).await {
+        Ok(uploads) => {
+            for upload in uploads {
+                delete_stored(&store, &upload).await;
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
@@ -1,30 +1,68 @@
 use axum::{
     extract::{Path, Query, State},
-    response::IntoResponse,
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
     routing::{delete, get, post},
     Json, Router,
 };
-use base64::{prelude::BASE64_STANDARD, Engine};
-use reqwest::{
-    multipart::{Form, Part},
-    Client, StatusCode,
-};
-use serde::Deserialize;
+use base64::{prelude::BASE64_URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::StatusCode;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 
 use crate::{
     auth::Claims,
-    db::{postgres::PostgresDb, Db, OrderInput},
-    routes::SearchQuery,
+    db::{
+        postgres::PostgresDb, CancelOutcome, Db, Order, OrderCursor, OrderFilter, OrderInput,
+        OrderNear, OrderSearch, OrderSort,
+    },
+    geo::{normalize_place, validate_coordinates, validate_radius},
+    money::{default_currency, validate_amount, validate_currency},
+    receipt::render_receipt,
+    routes::{
+        delete_private_files,
+        stats::viewer_key,
+        tags::normalize_tags,
+        uploads::{check_uploads, delete_stored},
+    },
+    storage::Storage,
     AppState,
 };
 
+/// Fields an offer depends on, and how they are called when telling bidders
+/// about changes.
+const MATERIAL_FIELDS: &[(&str, &str)] = &[
+    ("order_name", "name"),
+    ("order_desc", "description"),
+    ("price", "price"),
+    ("currency", "price"),
+    ("deadline", "deadline"),
+    ("remote", "location"),
+    ("latitude", "location"),
+    ("longitude", "location"),
+    ("city", "location"),
+    ("school", "location"),
+];
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+const PRICE_LOCKED: &str = "Price can't change once an offer is accepted";
+
 pub fn router() -> Router<AppState<PostgresDb>> {
     Router::new()
         .route("/order", post(create_order_handler))
+        .route("/orders", get(list_orders_handler))
         .route("/orders/user/{id}", get(get_orders_by_user_handler))
+        .route("/orders/drafts", get(get_drafts_handler))
         .route("/orders/{id}", get(get_order_handler))
         .route("/orders/{id}", post(update_order_handler))
         .route("/orders/{id}", delete(delete_order_handler))
+        .route("/orders/{id}/cancel", post(cancel_order_handler))
+        .route("/orders/{id}/publish", post(publish_order_handler))
+        .route("/orders/{id}/duplicate", post(duplicate_order_handler))
+        .route("/orders/{id}/receipt", get(get_receipt_handler))
         .route("/orders/search", get(search_orders_handler))
 }
 
@@ -32,62 +70,291 @@ pub fn router() -> Router<AppState<PostgresDb>> {
 struct OrderBody {
     order_name: String,
     order_desc: String,
-    price: f64,
-    images: Vec<String>, // Base 64
+    price: Decimal,
+    #[serde(default = "default_currency")]
+    currency: String,
+    category_id: Option<usize>,
+    #[serde(default)]
+    tags: Vec<String>,
+    deadline: Option<DateTime<Utc>>,
+    upload_ids: Vec<usize>, // From POST /uploads
+    /// Keep the order to yourself until it's published.
+    #[serde(default)]
+    draft: bool,
+    /// Publish automatically at this time, implies `draft`.
+    publish_at: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    location: LocationBody,
+}
+
+#[derive(Deserialize)]
+struct LocationBody {
+    #[serde(default)]
+    remote: bool,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    city: Option<String>,
+    school: Option<String>,
+}
+
+impl LocationBody {
+    fn validate(self) -> Result<Self, String> {
+        validate_coordinates(self.latitude, self.longitude)?;
+        Ok(LocationBody {
+            city: normalize_place(self.city)?,
+            school: normalize_place(self.school)?,
+            ..self
+        })
+    }
+}
+
+pub async fn check_category<D: Db>(db: &D, category_id: Option<usize>) -> Result<(), Response> {
+    let Some(category_id) = category_id else {
+        return Ok(());
+    };
+    match db.get_category_by_id(category_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err((StatusCode::BAD_REQUEST, "Unknown category").into_response()),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response()),
+    }
+}
+
+/// Parses a comma separated tag list from a query string.
+fn parse_tags(tags: Option<&str>) -> Result<Vec<String>, String> {
+    normalize_tags(
+        &tags
+            .unwrap_or_default()
+            .split(',')
+            .map(str::to_string)
+            .collect::<Vec<_>>(),
+    )
 }
 
 async fn create_order_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Json(body): Json<OrderBody>,
 ) -> impl IntoResponse {
-    let client = Client::builder()
-        .user_agent(concat!(
-            env!("CARGO_PKG_NAME"),
-            "/",
-            env!("CARGO_PKG_VERSION")
-        ))
-        .build()
-        .unwrap();
-    let mut image_urls = Vec::with_capacity(body.images.len());
-    for img in body.images {
-        let decoded = BASE64_STANDARD.decode(img).unwrap();
-        let extension = infer::get(&decoded).unwrap().extension();
-        let form = Form::new().text("reqtype", "fileupload").part(
-            "fileToUpload",
-            Part::stream(decoded).file_name(format!("image.{}", extension)),
-        );
-        let res = match client
-            .post("https://catbox.moe/user/api.php")
-            .multipart(form)
-            .send()
-            .await
-        {
-            Ok(res) => res,
-            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
-        };
-        image_urls.push(res.text().await.unwrap());
+    if let Err(e) = validate_amount(body.price).and(validate_currency(&body.currency)) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    if body
+        .publish_at
+        .is_some_and(|publish_at| publish_at <= Utc::now())
+    {
+        return (StatusCode::BAD_REQUEST, "publish_at must be in the future").into_response();
     }
+    if let Err(e) = check_uploads(&db, claims.sub, &body.upload_ids).await {
+        return e.into_response();
+    }
+    if let Err(response) = check_category(&db, body.category_id).await {
+        return response;
+    }
+    let tags = match normalize_tags(&body.tags) {
+        Ok(tags) => tags,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let location = match body.location.validate() {
+        Ok(location) => location,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
 
-    match db
+    let draft = body.draft || body.publish_at.is_some();
+    let order = match db
         .create_order(
             OrderInput {
                 order_name: body.order_name,
                 order_desc: body.order_desc,
                 price: body.price,
-                image_urls,
+                currency: body.currency,
+                category_id: body.category_id,
+                tags,
+                deadline: body.deadline,
+                remote: location.remote,
+                latitude: location.latitude,
+                longitude: location.longitude,
+                city: location.city,
+                school: location.school,
             },
+            &body.upload_ids,
             claims.sub,
+            draft,
         )
         .await
     {
-        Ok(order) => (StatusCode::CREATED, Json(order)).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        Ok(order) => order,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let order = match body.publish_at {
+        Some(publish_at) => match db.schedule_order(order.order_id, publish_at).await {
+            Ok(order) => order,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        },
+        None => order,
+    };
+    // The order is there either way, alerts are best effort
+    if !draft {
+        if let Err(e) = db.notify_saved_searches(order.order_id).await {
+            eprintln!("Could not match saved searches: {e}");
+        }
+    }
+    (StatusCode::CREATED, Json(order)).into_response()
+}
+
+#[derive(Deserialize)]
+struct ListQuery {
+    min_price: Option<Decimal>,
+    max_price: Option<Decimal>,
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    status: Option<String>,
+    category_id: Option<usize>,
+    tags: Option<String>, // Comma separated
+    user_id: Option<usize>,
+    remote: Option<bool>,
+    /// Together with `longitude` and `radius_km`, only orders around there.
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    radius_km: Option<f64>,
+    #[serde(default)]
+    sort: OrderSort,
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+struct OrderPage {
+    orders: Vec<Order>,
+    next_cursor: Option<String>,
+}
+
+/// The sort key of `order` in the form `list_orders` compares it in.
+fn cursor_key(order: &Order, sort: OrderSort) -> String {
+    match sort {
+        OrderSort::Newest => order
+            .created_at
+            .to_rfc3339_opts(SecondsFormat::Micros, true),
+        OrderSort::PriceAsc | OrderSort::PriceDesc => order.price.to_string(),
+        OrderSort::Deadline => order.deadline.map_or("infinity".to_string(), |deadline| {
+            deadline.to_rfc3339_opts(SecondsFormat::Micros, true)
+        }),
+        // Formatted to parse back into the exact same float
+        OrderSort::Distance => order.distance_km.unwrap_or_default().to_string(),
+    }
+}
+
+/// Whether a cursor key sent back by a client parses as the type `list_orders`
+/// casts it to for `sort`, so a tampered one can't make the query fail.
+fn valid_cursor_key(key: &str, sort: OrderSort) -> bool {
+    match sort {
+        OrderSort::Newest => DateTime::parse_from_rfc3339(key).is_ok(),
+        OrderSort::PriceAsc | OrderSort::PriceDesc => key.parse::<Decimal>().is_ok(),
+        OrderSort::Deadline => key == "infinity" || DateTime::parse_from_rfc3339(key).is_ok(),
+        OrderSort::Distance => key.parse::<f64>().is_ok_and(f64::is_finite),
     }
 }
 
+async fn list_orders_handler<D: Db>(
+    State(AppState { db, .. }): State<AppState<D>>,
+    Query(query): Query<ListQuery>,
+) -> impl IntoResponse {
+    let after = match query.cursor {
+        Some(cursor) => match BASE64_URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|e| e.to_string())
+            .and_then(|json| {
+                serde_json::from_slice::<OrderCursor>(&json).map_err(|e| e.to_string())
+            }) {
+            Ok(cursor) if valid_cursor_key(&cursor.key, query.sort) => Some(cursor),
+            _ => return (StatusCode::BAD_REQUEST, "Invalid cursor").into_response(),
+        },
+        None => None,
+    };
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let tags = match parse_tags(query.tags.as_deref()) {
+        Ok(tags) => tags,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let near = match (query.latitude, query.longitude, query.radius_km) {
+        (None, None, None) => None,
+        (Some(latitude), Some(longitude), Some(radius_km)) => {
+            if let Err(e) = validate_coordinates(Some(latitude), Some(longitude))
+                .and(validate_radius(radius_km))
+            {
+                return (StatusCode::BAD_REQUEST, e).into_response();
+            }
+            Some(OrderNear {
+                latitude,
+                longitude,
+                radius_km,
+            })
+        }
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "latitude, longitude and radius_km go together",
+            )
+                .into_response()
+        }
+    };
+    if query.sort == OrderSort::Distance && near.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Sorting by distance needs latitude, longitude and radius_km",
+        )
+            .into_response();
+    }
+
+    // Fetch one extra row to know whether there is another page
+    let mut orders = match db
+        .list_orders(&OrderFilter {
+            min_price: query.min_price,
+            max_price: query.max_price,
+            created_after: query.created_after,
+            created_before: query.created_before,
+            status: query.status,
+            category_id: query.category_id,
+            tags,
+            user_id: query.user_id,
+            remote: query.remote,
+            near,
+            sort: query.sort,
+            after,
+            limit: limit + 1,
+        })
+        .await
+    {
+        Ok(orders) => orders,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let next_cursor = if orders.len() > limit {
+        orders.truncate(limit);
+        orders.last().map(|order| {
+            let cursor = OrderCursor {
+                key: cursor_key(order, query.sort),
+                order_id: order.order_id,
+            };
+            BASE64_URL_SAFE_NO_PAD.encode(serde_json::to_vec(&cursor).unwrap())
+        })
+    } else {
+        None
+    };
+
+    (
+        StatusCode::OK,
+        Json(OrderPage {
+            orders,
+            next_cursor,
+        }),
+    )
+        .into_response()
+}
+
 async fn get_orders_by_user_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     match db.get_orders_by_user_id(id).await {
@@ -96,32 +363,134 @@ async fn get_orders_by_user_handler<D: Db>(
     }
 }
 
+async fn get_drafts_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_drafts_by_user_id(claims.sub).await {
+        Ok(orders) => (StatusCode::OK, Json(orders)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
 async fn get_order_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    claims: Option<Claims>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
     match db.get_order_by_id(id).await {
-        Ok(Some(order)) => (StatusCode::OK, Json(order)).into_response(),
+        // Drafts don't exist for anyone but their owner and whoever they
+        // are reserved for
+        Ok(Some(order))
+            if order.status == "draft"
+                && claims.as_ref().is_none_or(|claims| {
+                    claims.sub != order.user_id && Some(claims.sub) != order.invited_user_id
+                }) =>
+        {
+            StatusCode::NOT_FOUND.into_response()
+        }
+        // Hidden ones only for their owner
+        Ok(Some(order))
+            if order.hidden_at.is_some()
+                && claims
+                    .as_ref()
+                    .is_none_or(|claims| claims.sub != order.user_id) =>
+        {
+            StatusCode::NOT_FOUND.into_response()
+        }
+        Ok(Some(order)) => {
+            // Owners looking at their own order don't count as interest
+            let viewer = viewer_key(claims.as_ref(), &headers).filter(|_| {
+                claims
+                    .as_ref()
+                    .is_none_or(|claims| claims.sub != order.user_id)
+            });
+            if let Some(viewer) = viewer {
+                if let Err(e) = db.record_order_view(id, &viewer).await {
+                    eprintln!("Could not record a view of order {id}: {e}");
+                }
+            }
+            (StatusCode::OK, Json(order)).into_response()
+        }
         Ok(None) => StatusCode::NOT_FOUND.into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
 
+#[derive(Deserialize)]
+struct PublishBody {
+    /// Publish later instead of right away.
+    publish_at: Option<DateTime<Utc>>,
+}
+
+async fn publish_order_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    body: Option<Json<PublishBody>>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if order.status != "draft" {
+        return (StatusCode::CONFLICT, "Order is already published").into_response();
+    }
+
+    if let Some(publish_at) = body.and_then(|Json(body)| body.publish_at) {
+        if publish_at <= Utc::now() {
+            return (StatusCode::BAD_REQUEST, "publish_at must be in the future").into_response();
+        }
+        return match db.schedule_order(id, publish_at).await {
+            Ok(order) => (StatusCode::OK, Json(order)).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+        };
+    }
+    match db.publish_order(id).await {
+        Ok(Some(order)) => {
+            if let Err(e) = db.notify_saved_searches(order.order_id).await {
+                eprintln!("Could not match saved searches: {e}");
+            }
+            (StatusCode::OK, Json(order)).into_response()
+        }
+        Ok(None) => (StatusCode::CONFLICT, "Order is already published").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
 #[derive(Deserialize)]
 struct OrderUpdateBody {
     order_name: String,
     order_desc: String,
-    price: f64,
+    price: Decimal,
+    #[serde(default = "default_currency")]
+    currency: String,
+    category_id: Option<usize>,
+    #[serde(default)]
+    tags: Vec<String>,
+    deadline: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    location: LocationBody,
 }
 
 async fn update_order_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
     Json(OrderUpdateBody {
         order_name,
         order_desc,
         price,
+        currency,
+        category_id,
+        tags,
+        deadline,
+        location,
     }): Json<OrderUpdateBody>,
 ) -> impl IntoResponse {
     let order = match db.get_order_by_id(id).await {
@@ -132,6 +501,23 @@ async fn update_order_handler<D: Db>(
     if order.user_id != claims.sub {
         return StatusCode::FORBIDDEN.into_response();
     }
+    if (price != order.price || currency != order.currency) && !price_editable(&order) {
+        return (StatusCode::CONFLICT, PRICE_LOCKED).into_response();
+    }
+    if let Err(e) = validate_amount(price).and(validate_currency(&currency)) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    if let Err(response) = check_category(&db, category_id).await {
+        return response;
+    }
+    let tags = match normalize_tags(&tags) {
+        Ok(tags) => tags,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let location = match location.validate() {
+        Ok(location) => location,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
     match db
         .update_order(
             id,
@@ -139,19 +525,121 @@ async fn update_order_handler<D: Db>(
                 order_name,
                 order_desc,
                 price,
-                image_urls: order.image_urls,
+                currency,
+                category_id,
+                tags,
+                deadline,
+                remote: location.remote,
+                latitude: location.latitude,
+                longitude: location.longitude,
+                city: location.city,
+                school: location.school,
+            },
+        )
+        .await
+    {
+        Ok(Some(updated)) => {
+            if updated.price != order.price || updated.currency != order.currency {
+                let message = format!(
+                    "Price of \"{}\" changed from {:.2} {} to {:.2} {}",
+                    updated.order_name,
+                    order.price,
+                    order.currency,
+                    updated.price,
+                    updated.currency
+                );
+                if let Err(e) = db.notify_favorites(id, "price_changed", &message).await {
+                    eprintln!("Could not notify favorites: {e}");
+                }
+            }
+            let mut changed = Vec::new();
+            for change in order.changes(&updated) {
+                if let Some(&(_, label)) = MATERIAL_FIELDS
+                    .iter()
+                    .find(|(field, _)| *field == change.field)
+                {
+                    if !changed.contains(&label) {
+                        changed.push(label);
+                    }
+                }
+            }
+            if !changed.is_empty() {
+                let message = format!(
+                    "\"{}\" was edited after your offer, check its {}",
+                    updated.order_name,
+                    changed.join(", ")
+                );
+                if let Err(e) = db.notify_bidders(id, "order_edited", &message).await {
+                    eprintln!("Could not notify bidders: {e}");
+                }
+            }
+            (StatusCode::OK, Json(updated)).into_response()
+        }
+        Ok(None) => (StatusCode::CONFLICT, PRICE_LOCKED).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// The price is held in escrow from the moment an offer is accepted.
+fn price_editable(order: &Order) -> bool {
+    order.status == "draft" || order.status == "open"
+}
+
+/// Copies the order into a new draft. The images point at the same uploads,
+/// which stay around until no order uses them.
+async fn duplicate_order_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let upload_ids = order
+        .images
+        .iter()
+        .map(|image| image.upload_id)
+        .collect::<Vec<_>>();
+    match db
+        .create_order(
+            OrderInput {
+                order_name: order.order_name,
+                order_desc: order.order_desc,
+                price: order.price,
+                currency: order.currency,
+                category_id: order.category_id,
+                tags: order.tags,
+                deadline: None,
+                remote: order.remote,
+                latitude: order.latitude,
+                longitude: order.longitude,
+                city: order.city,
+                school: order.school,
             },
+            &upload_ids,
+            claims.sub,
+            true,
         )
         .await
     {
-        Ok(order) => (StatusCode::OK, Json(order)).into_response(),
+        Ok(order) => (StatusCode::CREATED, Json(order)).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
 
 async fn delete_order_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState {
+        db,
+        store,
+        private_store,
+        ..
+    }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     let order = match db.get_order_by_id(id).await {
@@ -162,18 +650,254 @@ async fn delete_order_handler<D: Db>(
     if order.user_id != claims.sub {
         return StatusCode::FORBIDDEN.into_response();
     }
-    match db.delete_order(id).await {
+    if order.status == "assigned" || order.status == "disputed" {
+        return (StatusCode::CONFLICT, "Cancel the order before deleting it").into_response();
+    }
+    match remove_order(&db, &store, &private_store, &order).await {
         Ok(()) => StatusCode::NO_CONTENT.into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
 
+/// Deletes the order along with its attachments, delivered files, dispute
+/// evidence and the images no other order or template uses.
+pub async fn remove_order<D: Db>(
+    db: &D,
+    store: &Storage,
+    private_store: &Storage,
+    order: &Order,
+) -> Result<(), String> {
+    // The rows go with the order, so find their files first
+    let mut keys = db
+        .get_attachments_by_order_id(order.order_id)
+        .await?
+        .into_iter()
+        .map(|attachment| attachment.storage_key)
+        .collect::<Vec<_>>();
+    for delivery in db.get_deliveries_by_order_id(order.order_id).await? {
+        keys.extend(delivery.files.into_iter().map(|file| file.storage_key));
+    }
+    for dispute in db.get_disputes_by_order_id(order.order_id).await? {
+        keys.extend(dispute.evidence.into_iter().map(|file| file.storage_key));
+    }
+    db.delete_order(order.order_id).await?;
+    delete_private_files(private_store, &keys).await;
+    let upload_ids = order
+        .images
+        .iter()
+        .map(|image| image.upload_id)
+        .collect::<Vec<_>>();
+    for upload in db.delete_unused_uploads(&upload_ids).await? {
+        delete_stored(store, &upload).await;
+    }
+    Ok(())
+}
+
+async fn cancel_order_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    match db.cancel_order(id).await {
+        Ok(CancelOutcome::Cancelled(order)) => (StatusCode::OK, Json(order)).into_response(),
+        Ok(CancelOutcome::NotCancellable) => {
+            (StatusCode::CONFLICT, "Order can no longer be cancelled").into_response()
+        }
+        Ok(CancelOutcome::Delivered) => (
+            StatusCode::CONFLICT,
+            "The contractor already delivered, open a dispute instead",
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn get_receipt_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let offer = match db.get_accepted_offer_by_order_id(id).await {
+        Ok(Some(offer)) => offer,
+        Ok(None) => return StatusCode::FORBIDDEN.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub && offer.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if order.status != "completed" {
+        return (StatusCode::CONFLICT, "Order is not completed").into_response();
+    }
+    let owner = match db.get_user_by_id(order.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let contractor = match db.get_user_by_id(offer.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => unreachable!(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let milestones = match db.get_milestones_by_offer_id(offer.offer_id).await {
+        Ok(milestones) => milestones,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let settlement = match db.get_resolved_dispute_by_order_id(id).await {
+        Ok(settlement) => settlement,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    // The price is what was actually paid into escrow, orders from before
+    // the ledger only have the listed one
+    let agreed_price = match db.get_escrow_hold(id).await {
+        Ok(held) => held.unwrap_or(order.price),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    (
+        StatusCode::OK,
+        [
+            (header::CONTENT_TYPE, "application/pdf".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"receipt-{}.pdf\"", id),
+            ),
+        ],
+        render_receipt(
+            &order,
+            agreed_price,
+            &owner,
+            &contractor,
+            &offer,
+            &milestones,
+            settlement.as_ref(),
+        ),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+struct OrderSearchQuery {
+    query: String,
+    category_id: Option<usize>,
+    tags: Option<String>, // Comma separated
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
 async fn search_orders_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
-    Query(query): Query<SearchQuery>,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Query(query): Query<OrderSearchQuery>,
 ) -> impl IntoResponse {
-    match db.search_orders(&query.query).await {
-        Ok(orders) => (StatusCode::OK, Json(orders)).into_response(),
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let tags = match parse_tags(query.tags.as_deref()) {
+        Ok(tags) => tags,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    match db
+        .search_orders(&OrderSearch {
+            query: query.query,
+            category_id: query.category_id,
+            tags,
+            limit,
+            offset: query.offset,
+        })
+        .await
+    {
+        Ok(results) => (StatusCode::OK, Json(results)).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const SORTS: [OrderSort; 5] = [
+        OrderSort::Newest,
+        OrderSort::PriceAsc,
+        OrderSort::PriceDesc,
+        OrderSort::Deadline,
+        OrderSort::Distance,
+    ];
+
+    fn order(deadline: Option<&str>) -> Order {
+        serde_json::from_value(json!({
+            "order_id": 1,
+            "user_id": 1,
+            "order_name": "Order",
+            "order_desc": "",
+            "price": "12.50",
+            "currency": "PLN",
+            "images": [],
+            "tags": [],
+            "deadline": deadline,
+            "remote": true,
+            "distance_km": 2.75,
+            "status": "open",
+            "created_at": "2026-01-02T03:04:05.123456Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn cursor_keys_pass_their_own_validation() {
+        for order in [order(None), order(Some("2026-02-01T12:00:00Z"))] {
+            for sort in SORTS {
+                assert!(valid_cursor_key(&cursor_key(&order, sort), sort));
+            }
+        }
+    }
+
+    #[test]
+    fn cursor_keys_keep_the_exact_sort_value() {
+        let order = order(None);
+        assert_eq!(
+            cursor_key(&order, OrderSort::Newest),
+            "2026-01-02T03:04:05.123456Z"
+        );
+        assert_eq!(cursor_key(&order, OrderSort::PriceAsc), "12.50");
+        assert_eq!(cursor_key(&order, OrderSort::Deadline), "infinity");
+        assert_eq!(cursor_key(&order, OrderSort::Distance), "2.75");
+    }
+
+    #[test]
+    fn rejects_cursor_keys_of_another_sort() {
+        assert!(!valid_cursor_key("12.50", OrderSort::Newest));
+        assert!(!valid_cursor_key("infinity", OrderSort::Newest));
+        assert!(!valid_cursor_key(
+            "2026-01-02T03:04:05Z",
+            OrderSort::PriceAsc
+        ));
+        assert!(!valid_cursor_key("12.50", OrderSort::Deadline));
+        assert!(!valid_cursor_key("far", OrderSort::Distance));
+    }
+
+    #[test]
+    fn rejects_distances_postgres_cant_compare() {
+        for key in ["NaN", "inf", "-inf"] {
+            assert!(
+                !valid_cursor_key(key, OrderSort::Distance),
+                "{key} was accepted"
+            );
+        }
+    }
+}
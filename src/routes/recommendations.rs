@@ -0,0 +1,63 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db},
+    recommend::rank,
+    AppState,
+};
+
+const DEFAULT_RECOMMENDATIONS: usize = 20;
+const MAX_RECOMMENDATIONS: usize = 50;
+
+/// How many of the newest open orders get ranked.
+const CANDIDATES: usize = 500;
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new().route("/orders/recommended", get(get_recommended_handler))
+}
+
+#[derive(Deserialize)]
+struct RecommendedQuery {
+    limit: Option<usize>,
+}
+
+async fn get_recommended_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Query(query): Query<RecommendedQuery>,
+) -> impl IntoResponse {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_RECOMMENDATIONS)
+        .clamp(1, MAX_RECOMMENDATIONS);
+    let user = match db.get_user_by_id(claims.sub).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let past_work = match db.get_past_work(claims.sub).await {
+        Ok(past_work) => past_work,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let candidates = match db
+        .get_recommendation_candidates(claims.sub, CANDIDATES)
+        .await
+    {
+        Ok(candidates) => candidates,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    (
+        StatusCode::OK,
+        Json(rank(&user.skills, &past_work, candidates, limit)),
+    )
+        .into_response()
+}
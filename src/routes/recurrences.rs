@@ -0,0 +1,201 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, AcceptOutcome, Db, OrderRecurrence},
+    recurrence::Rule,
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route(
+            "/orders/{id}/recurrence",
+            get(get_recurrence_handler)
+                .post(set_recurrence_handler)
+                .delete(delete_recurrence_handler),
+        )
+        .route("/orders/{id}/invite/accept", post(accept_invite_handler))
+        .route("/orders/{id}/invite/decline", post(decline_invite_handler))
+}
+
+#[derive(Deserialize)]
+struct RecurrenceBody {
+    rule: String, // e.g. FREQ=WEEKLY;BYDAY=MO
+    /// When the series starts, the order itself being its first occurrence.
+    /// Defaults to when the order was posted.
+    starts_at: Option<DateTime<Utc>>,
+    /// Reserve new orders for whoever took on the previous one.
+    #[serde(default)]
+    auto_offer: bool,
+}
+
+async fn set_recurrence_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(body): Json<RecurrenceBody>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if order.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let rule = match body.rule.parse::<Rule>() {
+        Ok(rule) => rule,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    // Changing the rule of a running series keeps its count
+    let existing = match db.get_order_recurrence(id).await {
+        Ok(existing) => existing,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let starts_at = body
+        .starts_at
+        .or(existing.as_ref().map(|existing| existing.starts_at))
+        .unwrap_or(order.created_at);
+    let last_at = existing
+        .as_ref()
+        .map(|existing| existing.last_at)
+        .unwrap_or(starts_at);
+    let occurrences = existing.map(|existing| existing.occurrences).unwrap_or(1);
+    let Some(next_at) = rule.next_after(starts_at, last_at.max(Utc::now()), occurrences) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            "The rule doesn't repeat after this order",
+        )
+            .into_response();
+    };
+
+    let recurrence = OrderRecurrence {
+        order_id: id,
+        rule: body.rule.trim().to_string(),
+        starts_at,
+        last_at,
+        next_at,
+        occurrences,
+        auto_offer: body.auto_offer,
+    };
+    match db.set_order_recurrence(&recurrence).await {
+        Ok(()) => (StatusCode::OK, Json(recurrence)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn get_recurrence_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.user_id == claims.sub => {}
+        Ok(Some(_)) => return StatusCode::FORBIDDEN.into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.get_order_recurrence(id).await {
+        Ok(Some(recurrence)) => (StatusCode::OK, Json(recurrence)).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn delete_recurrence_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.user_id == claims.sub => {}
+        Ok(Some(_)) => return StatusCode::FORBIDDEN.into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.delete_order_recurrence(id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Takes on an order reserved for the caller. The owner agreed to this by
+/// enabling `auto_offer`, so the offer is accepted right away.
+async fn accept_invite_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let order = match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.status == "draft" && order.invited_user_id == Some(claims.sub) => {
+            order
+        }
+        Ok(_) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let offer = match db.accept_invite(id, claims.sub).await {
+        Ok(AcceptOutcome::Accepted(offer)) => offer,
+        Ok(AcceptOutcome::NotOpen) => return StatusCode::NOT_FOUND.into_response(),
+        Ok(AcceptOutcome::InsufficientCredits) => {
+            return (
+                StatusCode::PAYMENT_REQUIRED,
+                "The owner doesn't have enough credits",
+            )
+                .into_response()
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    let message = format!("Your contractor took on \"{}\" again", order.order_name);
+    if let Err(e) = db
+        .notify_user(order.user_id, "invite_accepted", Some(id), &message)
+        .await
+    {
+        eprintln!("Could not notify the owner: {e}");
+    }
+    (StatusCode::OK, Json(offer)).into_response()
+}
+
+/// Turns down an order reserved for the caller, which publishes it.
+async fn decline_invite_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.status == "draft" && order.invited_user_id == Some(claims.sub) => {
+        }
+        Ok(_) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    // The invite may have been accepted or the order published meanwhile
+    let order = match db.publish_order(id).await {
+        Ok(Some(order)) => order,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if let Err(e) = db.notify_saved_searches(id).await {
+        eprintln!("Could not match saved searches: {e}");
+    }
+    let message = format!(
+        "Your previous contractor passed on \"{}\", it's public now",
+        order.order_name
+    );
+    if let Err(e) = db
+        .notify_user(order.user_id, "invite_declined", Some(id), &message)
+        .await
+    {
+        eprintln!("Could not notify the owner: {e}");
+    }
+    StatusCode::NO_CONTENT.into_response()
+}
@@ -0,0 +1,445 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    auth::Claims,
+    db::{
+        postgres::PostgresDb, Db, ModerationActionFilter, ModerationActionInput,
+        ModerationDecision, Report, ReportFilter, ReportInput, ReportTarget,
+    },
+    routes::{orders::remove_order, suspensions::suspend_user},
+    storage::Storage,
+    AppState,
+};
+
+const REASONS: &[&str] = &["scam", "spam", "offensive", "inappropriate", "other"];
+const MAX_DETAILS_LENGTH: usize = 1000;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 200;
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/reports", post(create_report_handler))
+        .route("/reports", get(get_reports_handler))
+        .route("/reports/{id}", get(get_report_handler))
+        .route("/reports/{id}/action", post(moderate_handler))
+        .route("/moderation/actions", get(get_actions_handler))
+}
+
+pub async fn require_moderator<D: Db>(db: &D, user_id: usize) -> Result<(), Response> {
+    match db.get_user_by_id(user_id).await {
+        Ok(Some(user)) if user.is_moderator() => Ok(()),
+        Ok(_) => Err(StatusCode::FORBIDDEN.into_response()),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response()),
+    }
+}
+
+/// The author of a report target and a copy of it for the audit log. `None`
+/// if it's gone or, given a `viewer`, not theirs to see.
+async fn load_target<D: Db>(
+    db: &D,
+    target_type: ReportTarget,
+    target_id: usize,
+    viewer: Option<usize>,
+) -> Result<Option<(usize, Value)>, String> {
+    let can_see = |visible: bool| viewer.is_none() || visible;
+    Ok(match target_type {
+        ReportTarget::Order => db
+            .get_order_by_id(target_id)
+            .await?
+            .filter(|order| {
+                can_see(
+                    order.hidden_at.is_none()
+                        && (order.status != "draft" || order.invited_user_id == viewer),
+                )
+            })
+            .map(|order| (order.user_id, json!(order))),
+        ReportTarget::Offer => db
+            .get_offer_by_id(target_id)
+            .await?
+            .map(|offer| (offer.user_id, json!(offer))),
+        ReportTarget::Message => db
+            .get_message_by_id(target_id)
+            .await?
+            .filter(|message| can_see(Some(message.receiver_id) == viewer))
+            .map(|message| (message.sender_id, json!(message))),
+        ReportTarget::Review => db
+            .get_review_by_id(target_id)
+            .await?
+            .map(|review| (review.user_reviewing, json!(review))),
+        // Without the email and password hash
+        ReportTarget::User => db.get_user_by_id(target_id).await?.map(|user| {
+            (
+                user.user_id,
+                json!({
+                    "user_id": user.user_id,
+                    "username": user.username,
+                    "skills": user.skills,
+                    "created_at": user.created_at,
+                }),
+            )
+        }),
+    })
+}
+
+#[derive(Deserialize)]
+struct ReportBody {
+    target_type: ReportTarget,
+    target_id: usize,
+    reason: String,
+    details: Option<String>,
+}
+
+async fn create_report_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Json(body): Json<ReportBody>,
+) -> impl IntoResponse {
+    if !REASONS.contains(&body.reason.as_str()) {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Reason must be one of: {}", REASONS.join(", ")),
+        )
+            .into_response();
+    }
+    let details = body
+        .details
+        .map(|details| details.trim().to_string())
+        .filter(|details| !details.is_empty());
+    if details
+        .as_ref()
+        .is_some_and(|details| details.chars().count() > MAX_DETAILS_LENGTH)
+    {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Details can have at most {MAX_DETAILS_LENGTH} characters"),
+        )
+            .into_response();
+    }
+    match load_target(&db, body.target_type, body.target_id, Some(claims.sub)).await {
+        Ok(Some((author, _))) if author == claims.sub => {
+            return (StatusCode::BAD_REQUEST, "You can't report yourself").into_response()
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+
+    match db
+        .create_report(ReportInput {
+            reporter_id: claims.sub,
+            target_type: body.target_type,
+            target_id: body.target_id,
+            reason: body.reason,
+            details,
+        })
+        .await
+    {
+        Ok(Some(report)) => (StatusCode::CREATED, Json(report)).into_response(),
+        Ok(None) => (StatusCode::CONFLICT, "You already reported this").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ReportsQuery {
+    status: Option<String>,
+    target_type: Option<ReportTarget>,
+    reason: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+/// The moderation queue, open reports unless asked for closed ones.
+async fn get_reports_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Query(query): Query<ReportsQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    match db
+        .get_reports(&ReportFilter {
+            status: query.status.unwrap_or("open".to_string()),
+            target_type: query.target_type,
+            reason: query.reason,
+            limit: query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+            offset: query.offset,
+        })
+        .await
+    {
+        Ok(reports) => (StatusCode::OK, Json(reports)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Serialize)]
+struct ReportDetails {
+    #[serde(flatten)]
+    report: Report,
+    /// The reported content as it is now, `None` once it's gone.
+    target: Option<Value>,
+}
+
+async fn get_report_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    let report = match db.get_report_by_id(id).await {
+        Ok(Some(report)) => report,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match load_target(&db, report.target_type, report.target_id, None).await {
+        Ok(target) => (
+            StatusCode::OK,
+            Json(ReportDetails {
+                report,
+                target: target.map(|(_, snapshot)| snapshot),
+            }),
+        )
+            .into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ActionBody {
+    action: ModerationDecision,
+    /// Shown to the author with warnings and as the reason for suspensions,
+    /// and kept in the audit log.
+    note: Option<String>,
+    /// How long to suspend for.
+    days: Option<u32>,
+}
+
+/// Decides on a report, which closes every open report on the same target.
+async fn moderate_handler<D: Db>(
+    claims: Claims,
+    State(AppState {
+        db,
+        store,
+        private_store,
+        ..
+    }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(body): Json<ActionBody>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    let report = match db.get_report_by_id(id).await {
+        Ok(Some(report)) if report.status == "open" => report,
+        Ok(Some(_)) => return (StatusCode::CONFLICT, "Report is already closed").into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let target = match load_target(&db, report.target_type, report.target_id, None).await {
+        Ok(target) => target,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let note = body
+        .note
+        .map(|note| note.trim().to_string())
+        .filter(|note| !note.is_empty());
+
+    let Some((author, snapshot)) = target else {
+        // Reports on content that's gone can still be closed
+        if body.action != ModerationDecision::Dismiss {
+            return (StatusCode::NOT_FOUND, "The reported content is gone").into_response();
+        }
+        return record_action(
+            &db,
+            claims.sub,
+            body.action,
+            &report,
+            None,
+            Value::Null,
+            note,
+        )
+        .await;
+    };
+    let result = match body.action {
+        ModerationDecision::Dismiss => Ok(()),
+        ModerationDecision::Hide if report.target_type == ReportTarget::User => {
+            return (
+                StatusCode::BAD_REQUEST,
+                "Profiles can't be hidden, ban the user instead",
+            )
+                .into_response()
+        }
+        ModerationDecision::Hide => db.hide_content(report.target_type, report.target_id).await,
+        ModerationDecision::Delete => {
+            match delete_target(
+                &db,
+                &store,
+                &private_store,
+                report.target_type,
+                report.target_id,
+            )
+            .await
+            {
+                Ok(result) => result,
+                Err(rejection) => return rejection.into_response(),
+            }
+        }
+        ModerationDecision::Warn => {
+            let message = format!(
+                "A moderator warned you about your {}: {}",
+                report.target_type.as_str(),
+                note.as_deref().unwrap_or(&report.reason)
+            );
+            let order_id = (report.target_type == ReportTarget::Order).then_some(report.target_id);
+            db.notify_user(author, "moderation_warning", order_id, &message)
+                .await
+        }
+        ModerationDecision::Suspend if body.days.is_none() => {
+            return (StatusCode::BAD_REQUEST, "Say how many days to suspend for").into_response()
+        }
+        ModerationDecision::Suspend | ModerationDecision::Ban => {
+            let days = body
+                .days
+                .filter(|_| body.action == ModerationDecision::Suspend);
+            let reason = note
+                .clone()
+                .unwrap_or_else(|| format!("Reported for {}", report.reason));
+            match suspend_user(&db, claims.sub, author, &reason, days).await {
+                Ok(_) => Ok(()),
+                Err(response) => return response,
+            }
+        }
+    };
+    if let Err(e) = result {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    record_action(
+        &db,
+        claims.sub,
+        body.action,
+        &report,
+        Some(author),
+        snapshot,
+        note,
+    )
+    .await
+}
+
+async fn record_action<D: Db>(
+    db: &D,
+    moderator_id: usize,
+    action: ModerationDecision,
+    report: &Report,
+    author: Option<usize>,
+    snapshot: Value,
+    note: Option<String>,
+) -> Response {
+    match db
+        .record_moderation_action(ModerationActionInput {
+            moderator_id,
+            action,
+            target_type: report.target_type,
+            target_id: report.target_id,
+            target_user_id: author,
+            snapshot,
+            note,
+        })
+        .await
+    {
+        Ok(action) => (StatusCode::CREATED, Json(action)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Deletes reported content, refusing where that would break an order in
+/// progress.
+async fn delete_target<D: Db>(
+    db: &D,
+    store: &Storage,
+    private_store: &Storage,
+    target_type: ReportTarget,
+    target_id: usize,
+) -> Result<Result<(), String>, (StatusCode, &'static str)> {
+    let in_progress = || {
+        Err((
+            StatusCode::CONFLICT,
+            "It's part of an order in progress, hide it instead",
+        ))
+    };
+    Ok(match target_type {
+        ReportTarget::Order => match db.get_order_by_id(target_id).await {
+            Ok(Some(order)) if order.status == "assigned" || order.status == "disputed" => {
+                return in_progress()
+            }
+            Ok(Some(order)) => remove_order(db, store, private_store, &order).await,
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        },
+        ReportTarget::Offer => match db.get_offer_by_id(target_id).await {
+            Ok(Some(offer)) if offer.status == "accepted" => return in_progress(),
+            Ok(_) => db.delete_offer(target_id).await,
+            Err(e) => Err(e),
+        },
+        ReportTarget::Message => db.delete_message(target_id).await,
+        ReportTarget::Review => db.delete_review(target_id).await,
+        ReportTarget::User => {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                "Users can't be deleted, ban them instead",
+            ))
+        }
+    })
+}
+
+#[derive(Deserialize)]
+struct ActionsQuery {
+    target_type: Option<ReportTarget>,
+    target_id: Option<usize>,
+    moderator_id: Option<usize>,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+/// The audit log of moderation decisions, newest first.
+async fn get_actions_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Query(query): Query<ActionsQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    match db
+        .get_moderation_actions(&ModerationActionFilter {
+            target_type: query.target_type,
+            target_id: query.target_id,
+            moderator_id: query.moderator_id,
+            limit: query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+            offset: query.offset,
+        })
+        .await
+    {
+        Ok(actions) => (StatusCode::OK, Json(actions)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
@@ -32,9 +32,23 @@ struct ReviewBody {
 
 async fn create_review_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Json(review): Json<ReviewBody>,
 ) -> impl IntoResponse {
+    match db
+        .has_completed_order_between(claims.sub, review.user_id)
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::FORBIDDEN,
+                "Reviews are only possible after a completed order",
+            )
+                .into_response()
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
     match db
         .create_review(ReviewInput {
             user_reviewed: review.user_id,
@@ -50,7 +64,7 @@ async fn create_review_handler<D: Db>(
 }
 
 async fn get_reviews_for_user_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     match db.get_reviews_for_user(id).await {
@@ -60,7 +74,7 @@ async fn get_reviews_for_user_handler<D: Db>(
 }
 
 async fn get_reviews_by_user_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     match db.get_reviews_by_user(id).await {
@@ -70,7 +84,7 @@ async fn get_reviews_by_user_handler<D: Db>(
 }
 
 async fn get_review_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     match db.get_review_by_id(id).await {
@@ -88,7 +102,7 @@ struct ReviewUpdateBody {
 
 async fn update_review_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
     Json(ReviewUpdateBody { rating, content }): Json<ReviewUpdateBody>,
 ) -> impl IntoResponse {
@@ -108,7 +122,7 @@ async fn update_review_handler<D: Db>(
 
 async fn delete_review_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     let review = match db.get_review_by_id(id).await {
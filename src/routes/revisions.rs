@@ -0,0 +1,50 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db},
+    AppState,
+};
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new().route("/orders/{id}/history", get(get_history_handler))
+}
+
+/// Edits made to the order since it was published, newest first.
+async fn get_history_handler<D: Db>(
+    claims: Option<Claims>,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order))
+            if order.status == "draft"
+                && claims.as_ref().is_none_or(|claims| {
+                    claims.sub != order.user_id && Some(claims.sub) != order.invited_user_id
+                }) =>
+        {
+            return StatusCode::NOT_FOUND.into_response()
+        }
+        Ok(Some(order))
+            if order.hidden_at.is_some()
+                && claims
+                    .as_ref()
+                    .is_none_or(|claims| claims.sub != order.user_id) =>
+        {
+            return StatusCode::NOT_FOUND.into_response()
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.get_order_revisions(id).await {
+        Ok(revisions) => (StatusCode::OK, Json(revisions)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
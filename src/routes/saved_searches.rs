@@ -0,0 +1,126 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db, SavedSearchInput},
+    money::validate_amount,
+    routes::{orders::check_category, tags::normalize_tags},
+    AppState,
+};
+
+const MAX_SAVED_SEARCHES: usize = 20;
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/saved-searches", post(create_saved_search_handler))
+        .route("/saved-searches", get(get_saved_searches_handler))
+        .route("/saved-searches/{id}", delete(delete_saved_search_handler))
+}
+
+#[derive(Deserialize)]
+struct SavedSearchBody {
+    name: String,
+    #[serde(default)]
+    query: String,
+    category_id: Option<usize>,
+    #[serde(default)]
+    tags: Vec<String>,
+    min_price: Option<Decimal>,
+    max_price: Option<Decimal>,
+    #[serde(default = "default_email_digest")]
+    email_digest: bool,
+}
+
+fn default_email_digest() -> bool {
+    true
+}
+
+async fn create_saved_search_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Json(body): Json<SavedSearchBody>,
+) -> impl IntoResponse {
+    let name = body.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 100 {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Name must be between 1 and 100 characters",
+        )
+            .into_response();
+    }
+    if let Err(e) = [body.min_price, body.max_price]
+        .into_iter()
+        .flatten()
+        .try_for_each(validate_amount)
+    {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    let tags = match normalize_tags(&body.tags) {
+        Ok(tags) => tags,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    if let Err(response) = check_category(&db, body.category_id).await {
+        return response;
+    }
+    match db.get_saved_searches_by_user_id(claims.sub).await {
+        Ok(searches) if searches.len() >= MAX_SAVED_SEARCHES => {
+            return (StatusCode::CONFLICT, "Too many saved searches").into_response()
+        }
+        Ok(_) => {}
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+
+    match db
+        .create_saved_search(
+            SavedSearchInput {
+                name,
+                query: body.query.trim().to_string(),
+                category_id: body.category_id,
+                tags,
+                min_price: body.min_price,
+                max_price: body.max_price,
+                email_digest: body.email_digest,
+            },
+            claims.sub,
+        )
+        .await
+    {
+        Ok(search) => (StatusCode::CREATED, Json(search)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn get_saved_searches_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_saved_searches_by_user_id(claims.sub).await {
+        Ok(searches) => (StatusCode::OK, Json(searches)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn delete_saved_search_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    match db.get_saved_search_by_id(id).await {
+        Ok(Some(search)) if search.user_id == claims.sub => {}
+        Ok(Some(_)) => return StatusCode::FORBIDDEN.into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.delete_saved_search(id).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
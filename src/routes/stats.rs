@@ -0,0 +1,65 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db},
+    AppState,
+};
+
+const MAX_SESSION_ID_LENGTH: usize = 64;
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/orders/{id}/stats", get(get_order_stats_handler))
+        .route("/user/me/stats", get(get_user_stats_handler))
+}
+
+/// Who to count a view for: the signed in user, or the anonymous session the
+/// client identifies with `X-Session-Id`. Views without either aren't counted.
+pub fn viewer_key(claims: Option<&Claims>, headers: &HeaderMap) -> Option<String> {
+    if let Some(claims) = claims {
+        return Some(format!("user:{}", claims.sub));
+    }
+    headers
+        .get("x-session-id")
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_SESSION_ID_LENGTH
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(|id| format!("session:{id}"))
+}
+
+async fn get_order_stats_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    match db.get_order_by_id(id).await {
+        Ok(Some(order)) if order.user_id == claims.sub => {}
+        Ok(Some(_)) => return StatusCode::FORBIDDEN.into_response(),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.get_order_stats(id).await {
+        Ok(stats) => (StatusCode::OK, Json(stats)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn get_user_stats_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_user_order_stats(claims.sub).await {
+        Ok(stats) => (StatusCode::OK, Json(stats)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
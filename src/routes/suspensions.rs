@@ -0,0 +1,312 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Appeal, Db, Suspension, SuspensionInput},
+    routes::{reports::require_moderator, user::verify_password},
+    AppState,
+};
+
+const MAX_REASON_LENGTH: usize = 1000;
+const MAX_APPEAL_LENGTH: usize = 2000;
+const MAX_SUSPENSION_DAYS: u32 = 365;
+
+const DEFAULT_PAGE_SIZE: usize = 50;
+const MAX_PAGE_SIZE: usize = 200;
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/user/{id}/suspensions", post(suspend_handler))
+        .route("/user/{id}/suspensions", get(get_suspensions_handler))
+        .route("/suspensions/{id}/lift", post(lift_handler))
+        .route("/appeals", post(appeal_handler))
+        .route("/appeals", get(get_appeals_handler))
+        .route("/appeals/{id}", get(get_appeal_handler))
+        .route("/appeals/{id}/review", post(review_handler))
+}
+
+/// Suspends `user_id` for `days`, or bans them for good without.
+pub async fn suspend_user<D: Db>(
+    db: &D,
+    moderator_id: usize,
+    user_id: usize,
+    reason: &str,
+    days: Option<u32>,
+) -> Result<Suspension, Response> {
+    let reason = reason.trim();
+    if reason.is_empty() || reason.chars().count() > MAX_REASON_LENGTH {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Reason must have 1 to {MAX_REASON_LENGTH} characters"),
+        )
+            .into_response());
+    }
+    if days.is_some_and(|days| days == 0 || days > MAX_SUSPENSION_DAYS) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Suspensions last 1 to {MAX_SUSPENSION_DAYS} days"),
+        )
+            .into_response());
+    }
+    match db.get_user_by_id(user_id).await {
+        Ok(Some(user)) if user.is_moderator() => {
+            return Err((StatusCode::CONFLICT, "Moderators can't be suspended").into_response())
+        }
+        Ok(Some(_)) => {}
+        Ok(None) => return Err(StatusCode::NOT_FOUND.into_response()),
+        Err(e) => return Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response()),
+    }
+    db.create_suspension(SuspensionInput {
+        user_id,
+        moderator_id,
+        reason: reason.to_string(),
+        ends_at: days.map(|days| Utc::now() + Duration::days(days.into())),
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e).into_response())
+}
+
+#[derive(Serialize)]
+struct SuspensionNotice {
+    message: String,
+    suspension_id: usize,
+    reason: String,
+    ends_at: Option<DateTime<Utc>>,
+    appeal: Option<Appeal>,
+}
+
+/// What a suspended user gets instead of a token when signing in.
+pub async fn suspension_notice<D: Db>(db: &D, suspension: Suspension) -> Response {
+    let appeal = match db
+        .get_appeal_by_suspension_id(suspension.suspension_id)
+        .await
+    {
+        Ok(appeal) => appeal,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let message = match suspension.ends_at {
+        Some(ends_at) => format!(
+            "This account is suspended until {}",
+            ends_at.format("%Y-%m-%d %H:%M UTC")
+        ),
+        None => "This account has been banned".to_string(),
+    };
+    (
+        StatusCode::FORBIDDEN,
+        Json(SuspensionNotice {
+            message,
+            suspension_id: suspension.suspension_id,
+            reason: suspension.reason,
+            ends_at: suspension.ends_at,
+            appeal,
+        }),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+struct SuspendBody {
+    reason: String,
+    /// Left out for a permanent ban.
+    days: Option<u32>,
+}
+
+async fn suspend_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(body): Json<SuspendBody>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    match suspend_user(&db, claims.sub, id, &body.reason, body.days).await {
+        Ok(suspension) => (StatusCode::CREATED, Json(suspension)).into_response(),
+        Err(response) => response,
+    }
+}
+
+async fn get_suspensions_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    match db.get_suspensions_by_user_id(id).await {
+        Ok(suspensions) => (StatusCode::OK, Json(suspensions)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn lift_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    match db.get_suspension_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db.lift_suspension(id, claims.sub).await {
+        Ok(Some(suspension)) => (StatusCode::OK, Json(suspension)).into_response(),
+        Ok(None) => (StatusCode::CONFLICT, "Suspension is no longer in force").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Suspended users can't get a token, so they appeal with their password.
+#[derive(Deserialize)]
+struct AppealBody {
+    username: String,
+    password: String,
+    message: String,
+}
+
+async fn appeal_handler<D: Db>(
+    State(AppState { db, .. }): State<AppState<D>>,
+    Json(body): Json<AppealBody>,
+) -> impl IntoResponse {
+    let message = body.message.trim();
+    if message.is_empty() || message.chars().count() > MAX_APPEAL_LENGTH {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Appeals must have 1 to {MAX_APPEAL_LENGTH} characters"),
+        )
+            .into_response();
+    }
+    let user = match db.get_user_by_username(&body.username).await {
+        Ok(Some(user)) if verify_password(&user, &body.password) => user,
+        Ok(_) => return StatusCode::UNAUTHORIZED.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    let suspension = match db.get_active_suspension(user.user_id).await {
+        Ok(Some(suspension)) => suspension,
+        Ok(None) => return (StatusCode::CONFLICT, "This account isn't suspended").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match db.create_appeal(suspension.suspension_id, message).await {
+        Ok(Some(appeal)) => (StatusCode::CREATED, Json(appeal)).into_response(),
+        Ok(None) => (StatusCode::CONFLICT, "This suspension was already appealed").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct AppealsQuery {
+    status: Option<String>,
+    limit: Option<usize>,
+    #[serde(default)]
+    offset: usize,
+}
+
+/// Appeals waiting for a decision unless asked for decided ones.
+async fn get_appeals_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Query(query): Query<AppealsQuery>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    match db
+        .get_appeals(
+            query.status.as_deref().unwrap_or("pending"),
+            query
+                .limit
+                .unwrap_or(DEFAULT_PAGE_SIZE)
+                .clamp(1, MAX_PAGE_SIZE),
+            query.offset,
+        )
+        .await
+    {
+        Ok(appeals) => (StatusCode::OK, Json(appeals)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Serialize)]
+struct AppealDetails {
+    #[serde(flatten)]
+    appeal: Appeal,
+    suspension: Suspension,
+}
+
+async fn get_appeal_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    let appeal = match db.get_appeal_by_id(id).await {
+        Ok(Some(appeal)) => appeal,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    match db.get_suspension_by_id(appeal.suspension_id).await {
+        Ok(Some(suspension)) => {
+            (StatusCode::OK, Json(AppealDetails { appeal, suspension })).into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ReviewBody {
+    accept: bool,
+    /// Shown to the user the next time they try to sign in.
+    response: Option<String>,
+}
+
+/// Accepting an appeal lifts the suspension.
+async fn review_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+    Json(body): Json<ReviewBody>,
+) -> impl IntoResponse {
+    if let Err(response) = require_moderator(&db, claims.sub).await {
+        return response;
+    }
+    let response = body
+        .response
+        .as_deref()
+        .map(str::trim)
+        .filter(|response| !response.is_empty());
+    if response.is_some_and(|response| response.chars().count() > MAX_APPEAL_LENGTH) {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("Responses can have at most {MAX_APPEAL_LENGTH} characters"),
+        )
+            .into_response();
+    }
+    match db.get_appeal_by_id(id).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    match db
+        .review_appeal(id, claims.sub, body.accept, response)
+        .await
+    {
+        Ok(Some(appeal)) => (StatusCode::OK, Json(appeal)).into_response(),
+        Ok(None) => (StatusCode::CONFLICT, "Appeal was already decided").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
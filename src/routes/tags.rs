@@ -0,0 +1,107 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::{
+    db::{postgres::PostgresDb, Db},
+    AppState,
+};
+
+pub const MAX_TAGS: usize = 10;
+const MAX_TAG_LENGTH: usize = 32;
+const SUGGESTIONS: usize = 10;
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new().route("/tags", get(autocomplete_handler))
+}
+
+/// Lowercases tags and collapses whitespace, dropping duplicates and empty ones.
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized = Vec::<String>::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase();
+        if tag.is_empty() || normalized.contains(&tag) {
+            continue;
+        }
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(format!(
+                "Tags can be at most {MAX_TAG_LENGTH} characters long"
+            ));
+        }
+        normalized.push(tag);
+    }
+    if normalized.len() > MAX_TAGS {
+        return Err(format!("An order can have at most {MAX_TAGS} tags"));
+    }
+    Ok(normalized)
+}
+
+#[derive(Deserialize)]
+struct AutocompleteQuery {
+    prefix: String,
+}
+
+async fn autocomplete_handler<D: Db>(
+    State(AppState { db, .. }): State<AppState<D>>,
+    Query(query): Query<AutocompleteQuery>,
+) -> impl IntoResponse {
+    let prefix = query.prefix.trim().to_lowercase();
+    match db.search_tags(&prefix, SUGGESTIONS).await {
+        Ok(tags) => (StatusCode::OK, Json(tags)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(tags: &[&str]) -> Vec<String> {
+        tags.iter().map(|tag| tag.to_string()).collect()
+    }
+
+    #[test]
+    fn lowercases_and_collapses_whitespace() {
+        assert_eq!(
+            normalize_tags(&tags(&["  3D   Printing ", "Math\ttutoring"])).unwrap(),
+            tags(&["3d printing", "math tutoring"])
+        );
+    }
+
+    #[test]
+    fn drops_empty_and_duplicate_tags() {
+        assert_eq!(
+            normalize_tags(&tags(&["math", "", "   ", "MATH", " Math "])).unwrap(),
+            tags(&["math"])
+        );
+    }
+
+    #[test]
+    fn limits_tag_length_in_characters() {
+        assert!(normalize_tags(&tags(&[&"ż".repeat(MAX_TAG_LENGTH)])).is_ok());
+        assert!(normalize_tags(&tags(&[&"a".repeat(MAX_TAG_LENGTH + 1)])).is_err());
+    }
+
+    #[test]
+    fn limits_the_number_of_distinct_tags() {
+        let many = (0..=MAX_TAGS)
+            .map(|i| format!("tag {i}"))
+            .collect::<Vec<_>>();
+        assert!(normalize_tags(&many[..MAX_TAGS]).is_ok());
+        assert!(normalize_tags(&many).is_err());
+
+        // Duplicates don't count towards the limit
+        let mut repeated = many[..MAX_TAGS].to_vec();
+        repeated.extend(many[..MAX_TAGS].iter().map(|tag| tag.to_uppercase()));
+        assert_eq!(normalize_tags(&repeated).unwrap().len(), MAX_TAGS);
+    }
+}
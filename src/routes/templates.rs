@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db, OrderInput, TemplateInput},
+    money::{default_currency, validate_amount, validate_currency},
+    routes::{
+        orders::check_category,
+        tags::normalize_tags,
+        uploads::{check_uploads, delete_stored},
+    },
+    AppState,
+};
+
+const MAX_TEMPLATES: usize = 50;
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/templates", post(create_template_handler))
+        .route("/templates", get(get_templates_handler))
+        .route("/templates/{id}", delete(delete_template_handler))
+        .route(
+            "/templates/{id}/order",
+            post(create_order_from_template_handler),
+        )
+}
+
+#[derive(Deserialize)]
+struct TemplateBody {
+    name: String,
+    order_name: String,
+    order_desc: String,
+    price: Decimal,
+    #[serde(default = "default_currency")]
+    currency: String,
+    category_id: Option<usize>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    upload_ids: Vec<usize>, // From POST /uploads
+}
+
+async fn create_template_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Json(body): Json<TemplateBody>,
+) -> impl IntoResponse {
+    let name = body.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 100 {
+        return (
+            StatusCode::BAD_REQUEST,
+            "Name must be between 1 and 100 characters",
+        )
+            .into_response();
+    }
+    if let Err(e) = validate_amount(body.price).and(validate_currency(&body.currency)) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+    if let Err(e) = check_uploads(&db, claims.sub, &body.upload_ids).await {
+        return e.into_response();
+    }
+    if let Err(response) = check_category(&db, body.category_id).await {
+        return response;
+    }
+    let tags = match normalize_tags(&body.tags) {
+        Ok(tags) => tags,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    match db.get_templates_by_user_id(claims.sub).await {
+        Ok(templates) if templates.len() >= MAX_TEMPLATES => {
+            return (StatusCode::CONFLICT, "Too many templates").into_response()
+        }
+        Ok(_) => {}
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+
+    match db
+        .create_template(
+            TemplateInput {
+                name,
+                order_name: body.order_name,
+                order_desc: body.order_desc,
+                price: body.price,
+                currency: body.currency,
+                category_id: body.category_id,
+                tags,
+            },
+            &body.upload_ids,
+            claims.sub,
+        )
+        .await
+    {
+        Ok(template) => (StatusCode::CREATED, Json(template)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn get_templates_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+) -> impl IntoResponse {
+    match db.get_templates_by_user_id(claims.sub).await {
+        Ok(templates) => (StatusCode::OK, Json(templates)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+async fn delete_template_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, store, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let template = match db.get_template_by_id(id).await {
+        Ok(Some(template)) => template,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if template.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    if let Err(e) = db.delete_template(id).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+
+    // Orders posted from the template keep their images
+    let upload_ids = template
+        .images
+        .iter()
+        .map(|image| image.upload_id)
+        .collect::<Vec<_>>();
+    match db.delete_unused_uploads(&upload_ids).await {
+        Ok(uploads) => {
+            for upload in uploads {
+                delete_stored(&store, &upload).await;
+            }
+            StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+/// Creates a draft from the template, to be given a deadline and published.
+async fn create_order_from_template_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let template = match db.get_template_by_id(id).await {
+        Ok(Some(template)) => template,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if template.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    let upload_ids = template
+        .images
+        .iter()
+        .map(|image| image.upload_id)
+        .collect::<Vec<_>>();
+    match db
+        .create_order(
+            OrderInput {
+                order_name: template.order_name,
+                order_desc: template.order_desc,
+                price: template.price,
+                currency: template.currency,
+                category_id: template.category_id,
+                tags: template.tags,
+                deadline: None,
+                remote: false,
+                latitude: None,
+                longitude: None,
+                city: None,
+                school: None,
+            },
+            &upload_ids,
+            claims.sub,
+            true,
+        )
+        .await
+    {
+        Ok(order) => (StatusCode::CREATED, Json(order)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
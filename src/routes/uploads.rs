@@ -0,0 +1,217 @@
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, post},
+    Json, Router,
+};
+
+use crate::{
+    auth::Claims,
+    db::{postgres::PostgresDb, Db, Upload, UploadInput},
+    images::process_image,
+    storage::{ImageStore, Storage},
+    AppState,
+};
+
+const MAX_FILE_SIZE: usize = 10 * 1024 * 1024;
+const MAX_FILES: usize = 10;
+const ALLOWED_TYPES: [&str; 4] = ["image/jpeg", "image/png", "image/webp", "image/gif"];
+
+pub fn router() -> Router<AppState<PostgresDb>> {
+    Router::new()
+        .route("/uploads/{id}", delete(delete_upload_handler))
+        .route(
+            "/uploads",
+            // Leave some room for the multipart boundaries and headers
+            post(upload_handler)
+                .layer(DefaultBodyLimit::max(MAX_FILES * MAX_FILE_SIZE + 64 * 1024)),
+        )
+}
+
+async fn upload_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, store, .. }): State<AppState<D>>,
+    multipart: Multipart,
+) -> impl IntoResponse {
+    let mut uploads = Vec::new();
+    if let Err(response) = store_uploads(&db, &store, claims.sub, multipart, &mut uploads).await {
+        // A request is stored as a whole or not at all
+        for upload in uploads {
+            match db.delete_upload(upload.upload_id).await {
+                Ok(true) => delete_stored(&store, &upload).await,
+                Ok(false) => {}
+                Err(e) => eprintln!("Could not delete upload {}: {e}", upload.upload_id),
+            }
+        }
+        return response;
+    }
+
+    (StatusCode::CREATED, Json(uploads)).into_response()
+}
+
+/// Processes and stores every image of the request, adding each one to
+/// `uploads` once it is saved.
+async fn store_uploads<D: Db>(
+    db: &D,
+    store: &Storage,
+    user_id: usize,
+    mut multipart: Multipart,
+    uploads: &mut Vec<Upload>,
+) -> Result<(), Response> {
+    loop {
+        let mut field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Err((e.status(), e.body_text()).into_response()),
+        };
+        if uploads.len() == MAX_FILES {
+            return Err((StatusCode::PAYLOAD_TOO_LARGE, "Too many files").into_response());
+        }
+        if field
+            .content_type()
+            .is_some_and(|content_type| !ALLOWED_TYPES.contains(&content_type))
+        {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Only images can be uploaded",
+            )
+                .into_response());
+        }
+
+        let mut data = Vec::new();
+        loop {
+            match field.chunk().await {
+                Ok(Some(chunk)) if data.len() + chunk.len() > MAX_FILE_SIZE => {
+                    return Err((StatusCode::PAYLOAD_TOO_LARGE, "File is too large").into_response())
+                }
+                Ok(Some(chunk)) => data.extend_from_slice(&chunk),
+                Ok(None) => break,
+                Err(e) => return Err((e.status(), e.body_text()).into_response()),
+            }
+        }
+
+        // Trust the bytes, not the header the client sent
+        if !infer::get(&data).is_some_and(|kind| ALLOWED_TYPES.contains(&kind.mime_type())) {
+            return Err((
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                "Only images can be uploaded",
+            )
+                .into_response());
+        }
+        let image = match tokio::task::spawn_blocking(move || process_image(&data)).await {
+            Ok(Ok(image)) => image,
+            Ok(Err(e)) => return Err((StatusCode::UNPROCESSABLE_ENTITY, e).into_response()),
+            Err(e) => {
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
+            }
+        };
+        let size = image.full.len();
+
+        let mut objects = Vec::with_capacity(3);
+        for data in [image.full, image.medium, image.thumbnail] {
+            match store.put(data, image.content_type).await {
+                Ok(object) => objects.push(object),
+                Err(e) => {
+                    for object in objects {
+                        let _ = store.delete(&object.key).await;
+                    }
+                    return Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response());
+                }
+            }
+        }
+        let Ok([full, medium, thumbnail]) = <[_; 3]>::try_from(objects) else {
+            unreachable!()
+        };
+        let keys = [full.key.clone(), medium.key.clone(), thumbnail.key.clone()];
+        match db
+            .create_upload(UploadInput {
+                user_id,
+                storage_key: full.key,
+                url: full.url,
+                medium_key: medium.key,
+                medium_url: medium.url,
+                thumbnail_key: thumbnail.key,
+                thumbnail_url: thumbnail.url,
+                content_type: image.content_type.to_string(),
+                size,
+            })
+            .await
+        {
+            Ok(upload) => uploads.push(upload),
+            Err(e) => {
+                for key in keys {
+                    let _ = store.delete(&key).await;
+                }
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn delete_upload_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, store, .. }): State<AppState<D>>,
+    Path(id): Path<usize>,
+) -> impl IntoResponse {
+    let upload = match db.get_uploads_by_ids(&[id]).await {
+        Ok(mut uploads) if !uploads.is_empty() => uploads.remove(0),
+        Ok(_) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+    if upload.user_id != claims.sub {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    match db.delete_upload(id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (
+                StatusCode::CONFLICT,
+                "Upload is used by an order or template",
+            )
+                .into_response()
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+    delete_stored(&store, &upload).await;
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// Makes sure every upload exists and belongs to `user_id`.
+pub async fn check_uploads<D: Db>(
+    db: &D,
+    user_id: usize,
+    upload_ids: &[usize],
+) -> Result<(), (StatusCode, String)> {
+    let uploads = db
+        .get_uploads_by_ids(upload_ids)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    for id in upload_ids {
+        if !uploads
+            .iter()
+            .any(|upload| upload.upload_id == *id && upload.user_id == user_id)
+        {
+            return Err((StatusCode::BAD_REQUEST, format!("Unknown upload {id}")));
+        }
+    }
+    Ok(())
+}
+
+/// Removes all sizes of an already deleted upload from the store.
+///
+/// The database row is gone at this point, so a failure only leaves an
+/// unreferenced file behind and is logged instead of failing the request.
+pub async fn delete_stored(store: &Storage, upload: &Upload) {
+    for key in [
+        &upload.storage_key,
+        &upload.medium_key,
+        &upload.thumbnail_key,
+    ] {
+        if let Err(e) = store.delete(key).await {
+            eprintln!("Could not delete stored file {key}: {e}");
+        }
+    }
+}
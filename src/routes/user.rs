@@ -10,20 +10,29 @@ use axum::{
     Json, Router,
 };
 
+use rust_decimal::Decimal;
 use serde::Deserialize;
 
 use crate::{
     auth::{create_jwt, Claims},
-    db::{postgres::PostgresDb, Db, UserInput},
-    routes::SearchQuery,
+    db::{postgres::PostgresDb, Db, User, UserInput},
+    routes::{
+        suspensions::suspension_notice,
+        tags::{normalize_tags, MAX_TAGS},
+        SearchQuery,
+    },
     AppState,
 };
 
+/// Credits every new account starts with.
+const STARTING_CREDITS: Decimal = Decimal::ONE_HUNDRED;
+
 pub fn router() -> Router<AppState<PostgresDb>> {
     Router::new()
         .route("/login", post(login_handler))
         .route("/register", post(register_handler))
         .route("/search", get(search_handler))
+        .route("/me/skills", post(set_skills_handler))
         .route("/{id}", get(get_user_handler))
         .route("/{id}", post(update_user_handler))
         .route("/{id}", delete(delete_user_handler))
@@ -36,7 +45,7 @@ struct LoginInput {
 }
 
 async fn login_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Json(login): Json<LoginInput>,
 ) -> impl IntoResponse {
     let user = match db.get_user_by_username(&login.username).await {
@@ -44,35 +53,41 @@ async fn login_handler<D: Db>(
         Ok(None) => return StatusCode::UNAUTHORIZED.into_response(),
         Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     };
-    if Argon2::default()
+    if !verify_password(&user, &login.password) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    match db.get_active_suspension(user.user_id).await {
+        Ok(Some(suspension)) => suspension_notice(&db, suspension).await,
+        Ok(None) => (StatusCode::OK, create_jwt(user.user_id)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
+pub fn verify_password(user: &User, password: &str) -> bool {
+    Argon2::default()
         .verify_password(
-            login.password.as_bytes(),
+            password.as_bytes(),
             &PasswordHash::new(&user.password_hash).unwrap(),
         )
         .is_ok()
-    {
-        (StatusCode::OK, create_jwt(user.user_id)).into_response()
-    } else {
-        StatusCode::UNAUTHORIZED.into_response()
-    }
 }
 
 async fn register_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Json(mut user): Json<UserInput>,
 ) -> impl IntoResponse {
     user.password = Argon2::default()
         .hash_password(user.password.as_bytes(), &SaltString::generate(&mut OsRng))
         .unwrap()
         .to_string();
-    match db.create_user(user).await {
+    match db.create_user(user, STARTING_CREDITS).await {
         Ok(user) => (StatusCode::CREATED, Json(user)).into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
 
 async fn search_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Query(query): Query<SearchQuery>,
 ) -> impl IntoResponse {
     match db.search_users(&query.query).await {
@@ -82,7 +97,7 @@ async fn search_handler<D: Db>(
 }
 
 async fn get_user_handler<D: Db>(
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     match db.get_user_by_id(id).await {
@@ -94,7 +109,7 @@ async fn get_user_handler<D: Db>(
 
 async fn update_user_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
     Json(user): Json<UserInput>,
 ) -> impl IntoResponse {
@@ -107,16 +122,49 @@ async fn update_user_handler<D: Db>(
     }
 }
 
+#[derive(Deserialize)]
+struct SkillsBody {
+    skills: Vec<String>,
+}
+
+/// Replaces the caller's skills, which are matched against order tags.
+async fn set_skills_handler<D: Db>(
+    claims: Claims,
+    State(AppState { db, .. }): State<AppState<D>>,
+    Json(body): Json<SkillsBody>,
+) -> impl IntoResponse {
+    if body.skills.len() > MAX_TAGS {
+        return (
+            StatusCode::BAD_REQUEST,
+            format!("A profile can have at most {MAX_TAGS} skills"),
+        )
+            .into_response();
+    }
+    let skills = match normalize_tags(&body.skills) {
+        Ok(skills) => skills,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    match db.set_user_skills(claims.sub, &skills).await {
+        Ok(user) => (StatusCode::OK, Json(user)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    }
+}
+
 async fn delete_user_handler<D: Db>(
     claims: Claims,
-    State(AppState { db }): State<AppState<D>>,
+    State(AppState { db, .. }): State<AppState<D>>,
     Path(id): Path<usize>,
 ) -> impl IntoResponse {
     if claims.sub != id {
         return StatusCode::FORBIDDEN.into_response();
     }
     match db.delete_user(id).await {
-        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => (
+            StatusCode::CONFLICT,
+            "Finish or settle your orders in progress first",
+        )
+            .into_response(),
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
     }
 }
@@ -0,0 +1,82 @@
+use shuttle_runtime::SecretStore;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+const EICAR: &[u8] = b"X5O!P%@AP[4\\PZX54(P^)7CC)7}$EICAR-STANDARD-ANTIVIRUS-TEST-FILE!$H+H*";
+
+pub enum ScanResult {
+    Clean,
+    Infected(String),
+}
+
+/// Virus scanner for attachments, picked with the `CLAMD_ADDR` secret.
+#[derive(Clone)]
+pub enum Scanner {
+    /// A clamd daemon listening on TCP, e.g. `localhost:3310`.
+    Clamd(String),
+    /// Stand-in used when no clamd is configured. It only recognises the
+    /// EICAR test file, which is enough to try out the rejection path locally.
+    Eicar,
+}
+
+impl Scanner {
+    pub fn from_secrets(secrets: &SecretStore) -> Self {
+        match secrets.get("CLAMD_ADDR") {
+            Some(addr) => Scanner::Clamd(addr),
+            None => Scanner::Eicar,
+        }
+    }
+
+    pub async fn scan(&self, data: &[u8]) -> Result<ScanResult, String> {
+        match self {
+            Scanner::Clamd(addr) => scan_clamd(addr, data).await,
+            Scanner::Eicar => Ok(if data.windows(EICAR.len()).any(|window| window == EICAR) {
+                ScanResult::Infected("Eicar-Test-Signature".to_string())
+            } else {
+                ScanResult::Clean
+            }),
+        }
+    }
+}
+
+/// Streams `data` to clamd with the `INSTREAM` command.
+async fn scan_clamd(addr: &str, data: &[u8]) -> Result<ScanResult, String> {
+    let mut stream = TcpStream::connect(addr).await.map_err(|e| e.to_string())?;
+    stream
+        .write_all(b"zINSTREAM\0")
+        .await
+        .map_err(|e| e.to_string())?;
+    for chunk in data.chunks(CHUNK_SIZE) {
+        stream
+            .write_all(&(chunk.len() as u32).to_be_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+        stream.write_all(chunk).await.map_err(|e| e.to_string())?;
+    }
+    stream
+        .write_all(&0u32.to_be_bytes())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // The reply ends with a null byte since the command was `z` prefixed
+    let mut reply = Vec::new();
+    let mut reader = BufReader::new(stream);
+    reader
+        .read_until(b'\0', &mut reply)
+        .await
+        .map_err(|e| e.to_string())?;
+    let reply = String::from_utf8_lossy(&reply);
+    // Replies look like `stream: OK` or `stream: <signature> FOUND`
+    let reply = reply.trim_end_matches(['\0', '\n']);
+    let verdict = reply.strip_prefix("stream: ").unwrap_or(reply);
+    if verdict == "OK" {
+        Ok(ScanResult::Clean)
+    } else if let Some(signature) = verdict.strip_suffix(" FOUND") {
+        Ok(ScanResult::Infected(signature.to_string()))
+    } else {
+        Err(format!("Unexpected clamd reply: {reply}"))
+    }
+}
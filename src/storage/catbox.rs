@@ -0,0 +1,71 @@
+use reqwest::{
+    multipart::{Form, Part},
+    Client,
+};
+
+use crate::storage::{new_key, ImageStore, StoredObject};
+
+/// Anonymous uploads to catbox.moe. Files can't be deleted once uploaded.
+#[derive(Clone)]
+pub struct CatboxStore {
+    client: Client,
+}
+
+impl CatboxStore {
+    pub fn new() -> Result<Self, String> {
+        Ok(Self {
+            client: Client::builder()
+                .user_agent(concat!(
+                    env!("CARGO_PKG_NAME"),
+                    "/",
+                    env!("CARGO_PKG_VERSION")
+                ))
+                .build()
+                .map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+impl ImageStore for CatboxStore {
+    async fn put(&self, data: Vec<u8>, content_type: &str) -> Result<StoredObject, String> {
+        // Catbox takes the extension from the name
+        let form = Form::new().text("reqtype", "fileupload").part(
+            "fileToUpload",
+            Part::stream(data).file_name(new_key(content_type)),
+        );
+        let res = self
+            .client
+            .post("https://catbox.moe/user/api.php")
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .error_for_status()
+            .map_err(|e| e.to_string())?;
+        let url = res.text().await.map_err(|e| e.to_string())?;
+        Ok(StoredObject {
+            key: url.clone(),
+            url,
+        })
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        // The key is the public URL of the file
+        self.client
+            .get(key)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .error_for_status()
+            .map_err(|e| e.to_string())?
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        eprintln!("Catbox does not support deleting {key}, leaving it in place");
+        Ok(())
+    }
+}
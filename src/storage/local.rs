@@ -0,0 +1,96 @@
+use std::path::PathBuf;
+
+use axum::{
+    http::{header, HeaderValue},
+    middleware::map_response,
+    response::Response,
+    Router,
+};
+use tower_http::services::ServeDir;
+
+use crate::storage::{new_key, ImageStore, StoredObject};
+
+/// Types a browser may show inline, everything else is downloaded.
+const INLINE_TYPES: [&str; 4] = ["image/jpeg", "image/png", "image/webp", "image/gif"];
+
+/// Stores files in a directory on disk, served by the app under `public_url`.
+#[derive(Clone)]
+pub struct LocalStore {
+    dir: PathBuf,
+    public_url: String,
+}
+
+impl LocalStore {
+    pub fn new(dir: impl Into<PathBuf>, public_url: String) -> Self {
+        Self {
+            dir: dir.into(),
+            public_url: public_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Serves the stored files, to be nested under `public_url`.
+    pub fn router(&self) -> Router {
+        Router::new()
+            .fallback_service(ServeDir::new(&self.dir))
+            .layer(map_response(file_headers))
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, String> {
+        // Keys are generated by us, anything else is not ours to touch
+        if key.contains(['/', '\\']) || key.starts_with('.') {
+            return Err(format!("Invalid key {key}"));
+        }
+        Ok(self.dir.join(key))
+    }
+}
+
+impl ImageStore for LocalStore {
+    async fn put(&self, data: Vec<u8>, content_type: &str) -> Result<StoredObject, String> {
+        let key = new_key(content_type);
+        tokio::fs::create_dir_all(&self.dir)
+            .await
+            .map_err(|e| e.to_string())?;
+        tokio::fs::write(self.dir.join(&key), data)
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(StoredObject {
+            url: format!("{}/{}", self.public_url, key),
+            key,
+        })
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        tokio::fs::read(self.path(key)?)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Uploaded files share our origin, so browsers must neither guess their type
+/// nor render anything but images.
+async fn file_headers(mut response: Response) -> Response {
+    let headers = response.headers_mut();
+    headers.insert(
+        header::X_CONTENT_TYPE_OPTIONS,
+        HeaderValue::from_static("nosniff"),
+    );
+    let inline = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| INLINE_TYPES.contains(&content_type));
+    if !inline {
+        headers.insert(
+            header::CONTENT_DISPOSITION,
+            HeaderValue::from_static("attachment"),
+        );
+    }
+    response
+}
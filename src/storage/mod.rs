@@ -0,0 +1,145 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use shuttle_runtime::SecretStore;
+
+pub mod catbox;
+pub mod local;
+pub mod s3;
+
+pub use catbox::CatboxStore;
+pub use local::LocalStore;
+pub use s3::S3Store;
+
+pub struct StoredObject {
+    /// Backend specific handle used to delete the object later.
+    pub key: String,
+    pub url: String,
+}
+
+pub trait ImageStore {
+    /// Stores `data` under a new key. `content_type` has to come from the
+    /// bytes, it decides how the file is served.
+    async fn put(&self, data: Vec<u8>, content_type: &str) -> Result<StoredObject, String>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String>;
+    async fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+/// The configured storage backend, picked with the `IMAGE_STORE` secret and
+/// local unless set. Catbox has to be asked for, files put there are public
+/// and can't be deleted again.
+#[derive(Clone)]
+pub enum Storage {
+    Local(LocalStore),
+    S3(S3Store),
+    Catbox(CatboxStore),
+}
+
+impl Storage {
+    pub fn from_secrets(secrets: &SecretStore) -> Result<Self, String> {
+        match secrets.get("IMAGE_STORE").as_deref().unwrap_or("local") {
+            "local" => Ok(Storage::Local(LocalStore::new(
+                secrets
+                    .get("LOCAL_STORE_DIR")
+                    .unwrap_or_else(|| "uploads".to_string()),
+                secrets
+                    .get("LOCAL_STORE_URL")
+                    .unwrap_or_else(|| "/files".to_string()),
+            ))),
+            "s3" => {
+                let get = |key: &str| secrets.get(key).ok_or(format!("Missing secret {key}"));
+                Ok(Storage::S3(S3Store::new(
+                    &get("S3_ENDPOINT")?,
+                    get("S3_BUCKET")?,
+                    secrets
+                        .get("S3_REGION")
+                        .unwrap_or_else(|| "us-east-1".to_string()),
+                    get("S3_ACCESS_KEY")?,
+                    get("S3_SECRET_KEY")?,
+                    secrets.get("S3_PUBLIC_URL"),
+                )?))
+            }
+            "catbox" => Ok(Storage::Catbox(CatboxStore::new()?)),
+            other => Err(format!("Unknown image store {other}")),
+        }
+    }
+
+    /// Where private files such as attachments go: the `S3_PRIVATE_BUCKET`
+    /// when using S3, a directory that is never served otherwise. Files in
+    /// it are only handed out by the app, after checking who is asking.
+    pub fn private_from_secrets(secrets: &SecretStore) -> Result<Self, String> {
+        match secrets.get("IMAGE_STORE").as_deref() {
+            Some("s3") => {
+                let get = |key: &str| secrets.get(key).ok_or(format!("Missing secret {key}"));
+                Ok(Storage::S3(S3Store::new(
+                    &get("S3_ENDPOINT")?,
+                    get("S3_PRIVATE_BUCKET")?,
+                    secrets
+                        .get("S3_REGION")
+                        .unwrap_or_else(|| "us-east-1".to_string()),
+                    get("S3_ACCESS_KEY")?,
+                    get("S3_SECRET_KEY")?,
+                    None,
+                )?))
+            }
+            _ => Ok(Storage::Local(LocalStore::new(
+                secrets
+                    .get("PRIVATE_STORE_DIR")
+                    .unwrap_or_else(|| "private".to_string()),
+                String::new(),
+            ))),
+        }
+    }
+}
+
+impl ImageStore for Storage {
+    async fn put(&self, data: Vec<u8>, content_type: &str) -> Result<StoredObject, String> {
+        match self {
+            Storage::Local(store) => store.put(data, content_type).await,
+            Storage::S3(store) => store.put(data, content_type).await,
+            Storage::Catbox(store) => store.put(data, content_type).await,
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        match self {
+            Storage::Local(store) => store.get(key).await,
+            Storage::S3(store) => store.get(key).await,
+            Storage::Catbox(store) => store.get(key).await,
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        match self {
+            Storage::Local(store) => store.delete(key).await,
+            Storage::S3(store) => store.delete(key).await,
+            Storage::Catbox(store) => store.delete(key).await,
+        }
+    }
+}
+
+/// Generates a random object key with the extension of `content_type`.
+///
+/// Stores serve files by extension, so it never comes from the client's file
+/// name: an upload named `x.html` would otherwise be served as a page on our
+/// origin. Types without a known extension get none and are served as
+/// `application/octet-stream`.
+pub fn new_key(content_type: &str) -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    match extension(content_type) {
+        Some(ext) => format!("{}.{}", hex::encode(bytes), ext),
+        None => hex::encode(bytes),
+    }
+}
+
+fn extension(content_type: &str) -> Option<&'static str> {
+    match content_type {
+        "image/jpeg" => Some("jpg"),
+        "image/png" => Some("png"),
+        "image/webp" => Some("webp"),
+        "image/gif" => Some("gif"),
+        "application/pdf" => Some("pdf"),
+        "application/zip" => Some("zip"),
+        "text/plain" => Some("txt"),
+        _ => None,
+    }
+}
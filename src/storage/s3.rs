@@ -0,0 +1,191 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, Url};
+use sha2::{Digest, Sha256};
+
+use crate::storage::{new_key, ImageStore, StoredObject};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Any S3-compatible object store, addressed path-style (`endpoint/bucket/key`).
+///
+/// Path-style addressing is what MinIO expects, so a local MinIO container
+/// works as a stand-in with `S3_ENDPOINT = "http://localhost:9000"` and its
+/// root credentials as the access and secret key.
+#[derive(Clone)]
+pub struct S3Store {
+    client: Client,
+    endpoint: Url,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+    public_url: String,
+}
+
+impl S3Store {
+    pub fn new(
+        endpoint: &str,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+        public_url: Option<String>,
+    ) -> Result<Self, String> {
+        let endpoint = Url::parse(endpoint).map_err(|e| e.to_string())?;
+        let public_url = public_url
+            .unwrap_or_else(|| format!("{}/{}", endpoint.as_str().trim_end_matches('/'), bucket))
+            .trim_end_matches('/')
+            .to_string();
+        Ok(Self {
+            client: Client::new(),
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+            public_url,
+        })
+    }
+
+    /// Sends a request for `key` signed with AWS Signature Version 4.
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        body: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<reqwest::Response, String> {
+        let path = format!(
+            "{}/{}/{}",
+            self.endpoint.path().trim_end_matches('/'),
+            uri_encode(&self.bucket),
+            uri_encode(key)
+        );
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut signing_key = format!("AWS4{}", self.secret_key).into_bytes();
+        for part in [date.as_str(), &self.region, "s3", "aws4_request"] {
+            signing_key = hmac(&signing_key, part.as_bytes());
+        }
+        let signature = hex::encode(hmac(&signing_key, string_to_sign.as_bytes()));
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key, scope, signed_headers, signature
+                ),
+            );
+        if let Some(content_type) = content_type {
+            request = request.header("content-type", content_type);
+        }
+        request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .error_for_status()
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl ImageStore for S3Store {
+    async fn put(&self, data: Vec<u8>, content_type: &str) -> Result<StoredObject, String> {
+        let key = new_key(content_type);
+        self.send(Method::PUT, &key, data, Some(content_type))
+            .await?;
+        Ok(StoredObject {
+            url: format!("{}/{}", self.public_url, key),
+            key,
+        })
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        self.send(Method::GET, key, Vec::new(), None)
+            .await?
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(|e| e.to_string())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        self.send(Method::DELETE, key, Vec::new(), None).await?;
+        Ok(())
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Percent-encodes a path segment the way SigV4 canonical requests expect.
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs against the MinIO from `docker-compose.yml`:
+    /// `docker compose up -d` and `cargo test -- --ignored minio`.
+    #[tokio::test]
+    #[ignore]
+    async fn round_trip_against_minio() {
+        let env = |key: &str, default: &str| std::env::var(key).unwrap_or(default.to_string());
+        let store = S3Store::new(
+            &env("S3_ENDPOINT", "http://localhost:9000"),
+            env("S3_BUCKET", "uploads"),
+            "us-east-1".to_string(),
+            env("S3_ACCESS_KEY", "minioadmin"),
+            env("S3_SECRET_KEY", "minioadmin"),
+            None,
+        )
+        .unwrap();
+
+        let object = store.put(b"hello".to_vec(), "text/plain").await.unwrap();
+        assert!(object.key.ends_with(".txt"));
+        assert!(object.url.ends_with(&object.key));
+        assert_eq!(store.get(&object.key).await.unwrap(), b"hello");
+        store.delete(&object.key).await.unwrap();
+        assert!(store.get(&object.key).await.is_err());
+    }
+}